@@ -0,0 +1,102 @@
+/* Generated by `cargo run --features std --bin generate-header`, do not edit. */
+
+#ifndef BUMP_MALLOC_FREE_H
+#define BUMP_MALLOC_FREE_H
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+typedef enum {
+    BUMP_ACTION_FREE = 0,
+    BUMP_ACTION_MALLOC = 1,
+    BUMP_ACTION_ERROR = 2,
+    BUMP_ACTION_QUERY = 3,
+    BUMP_ACTION_REALLOC = 4,
+    BUMP_ACTION_OVERFLOW = 5,
+    BUMP_ACTION_RELEASE = 6,
+    BUMP_ACTION_RESET = 7,
+    BUMP_ACTION_INVALID_FREE = 8,
+    BUMP_ACTION_DOUBLE_FREE = 9,
+} bump_action_t;
+
+typedef enum {
+    BUMP_REASON_NONE = 0,
+    BUMP_REASON_OUT_OF_MEMORY = 1,
+    BUMP_REASON_OVERFLOW = 2,
+    BUMP_REASON_INVALID_ALIGNMENT = 3,
+    BUMP_REASON_INVALID_FREE = 4,
+    BUMP_REASON_INVALID_REALLOC = 5,
+    BUMP_REASON_DOUBLE_FREE = 6,
+    BUMP_REASON_OUT_OF_ORDER = 7,
+    BUMP_REASON_IN_USE = 8,
+} bump_reason_t;
+
+typedef struct {
+    bump_action_t action;
+    size_t count;
+    size_t usage;
+    size_t maximum_usage;
+    void *ptr;
+    size_t requested_size;
+    size_t rounded_size;
+    bump_reason_t reason;
+} bump_status_t;
+
+typedef struct {
+    size_t head;
+    size_t blocks;
+    size_t outer_head;
+    size_t outer_blocks;
+    size_t depth;
+} bump_marker_t;
+
+typedef void (*bump_on_changed_c_t)(void *, const bump_status_t *);
+typedef void (*bump_on_drop_without_free_c_t)(void *, size_t);
+
+typedef void *(*bump_malloc_fn_t)(void *, size_t);
+typedef void (*bump_free_fn_t)(void *, void *);
+typedef void *(*bump_realloc_fn_t)(void *, void *, size_t);
+typedef bump_status_t (*bump_status_fn_t)(void *);
+typedef void *(*bump_calloc_fn_t)(void *, size_t, size_t);
+typedef void *(*bump_aligned_malloc_fn_t)(void *, size_t, size_t);
+typedef bump_marker_t (*bump_mark_fn_t)(void *);
+typedef int (*bump_release_fn_t)(void *, bump_marker_t);
+typedef void (*bump_on_changed_fn_t)(void *, bump_on_changed_c_t, void *);
+typedef void (*bump_on_drop_without_free_fn_t)(void *, bump_on_drop_without_free_c_t, void *);
+
+typedef struct {
+    void *context;
+    bump_malloc_fn_t malloc;
+    bump_free_fn_t free;
+    bump_realloc_fn_t realloc;
+    bump_status_fn_t status;
+    bump_calloc_fn_t calloc;
+    bump_aligned_malloc_fn_t aligned_malloc;
+    bump_mark_fn_t mark;
+    bump_release_fn_t release;
+    bump_on_changed_fn_t on_changed;
+    bump_on_drop_without_free_fn_t on_drop_without_free;
+} bump_allocator_t;
+
+void *bump_malloc(const bump_allocator_t *handle, size_t size);
+void bump_free(const bump_allocator_t *handle, void *ptr);
+void *bump_calloc(const bump_allocator_t *handle, size_t nmemb, size_t size);
+void *bump_realloc(const bump_allocator_t *handle, void *ptr, size_t size);
+void *bump_aligned_alloc(const bump_allocator_t *handle, size_t alignment, size_t size);
+int bump_posix_memalign(const bump_allocator_t *handle, void **memptr, size_t alignment, size_t size);
+bump_status_t bump_status(const bump_allocator_t *handle);
+bump_marker_t bump_mark(const bump_allocator_t *handle);
+int bump_release(const bump_allocator_t *handle, bump_marker_t marker);
+void bump_handle_on_changed(const bump_allocator_t *handle, bump_on_changed_c_t observer, void *context);
+void bump_handle_drop_without_free(const bump_allocator_t *handle, bump_on_drop_without_free_c_t handler, void *context);
+const bump_allocator_t *bump_create(void *buffer, size_t size, size_t alignment);
+size_t bump_destroy(const bump_allocator_t *handle);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* BUMP_MALLOC_FREE_H */
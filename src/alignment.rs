@@ -0,0 +1,46 @@
+//! Maps an `ALIGNMENT` const parameter to a type with that alignment.
+//!
+//! `#[repr(align)]` only accepts literals, so each supported alignment gets its own
+//! zero-sized archetype. `Bump` requires `Alignment<ALIGNMENT>: Aligned`, which also
+//! limits `ALIGNMENT` to the powers of two listed below.
+
+pub struct Alignment<const ALIGNMENT: usize>;
+
+pub trait Aligned {
+    type Archetype: Copy;
+}
+
+macro_rules! aligned {
+    ($($alignment:literal => $archetype:ident),*) => {
+        $(
+            #[doc(hidden)]
+            #[derive(Copy, Clone)]
+            #[repr(align($alignment))]
+            pub struct $archetype;
+
+            impl Aligned for Alignment<$alignment> {
+                type Archetype = $archetype;
+            }
+        )*
+    };
+}
+
+aligned!(
+    1 => Align1,
+    2 => Align2,
+    4 => Align4,
+    8 => Align8,
+    16 => Align16,
+    32 => Align32,
+    64 => Align64,
+    128 => Align128,
+    256 => Align256,
+    512 => Align512,
+    1024 => Align1024,
+    2048 => Align2048,
+    4096 => Align4096,
+    8192 => Align8192,
+    16384 => Align16384,
+    32768 => Align32768,
+    65536 => Align65536
+);
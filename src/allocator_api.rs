@@ -0,0 +1,108 @@
+//! `core::alloc::Allocator` for shared references to the allocators, behind the nightly
+//! `allocator_api` feature.
+
+use core::alloc::{AllocError, Allocator, Layout};
+use core::ffi::c_void;
+use core::ptr::NonNull;
+
+use crate::{Aligned, Alignment, Bump, BumpRegion, MallocFree};
+
+fn block(ptr: *mut c_void, size: usize) -> Result<NonNull<[u8]>, AllocError> {
+    NonNull::new(ptr as *mut u8)
+        .map(|ptr| NonNull::slice_from_raw_parts(ptr, size))
+        .ok_or(AllocError)
+}
+
+fn allocate<M: MallocFree>(malloc_free: &M, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+    block(malloc_free.aligned_malloc(layout.size(), layout.align()), layout.size())
+}
+
+unsafe fn resize<M: MallocFree>(malloc_free: &M, ptr: NonNull<u8>, new_layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+    //the most recent block is resized in place
+    let result = malloc_free.aligned_realloc(ptr.as_ptr() as *mut c_void, new_layout.size(), new_layout.align());
+    block(result, new_layout.size())
+}
+
+macro_rules! allocator {
+    ($([$($generic:tt)*] $type:ty where [$($bound:tt)*];)*) => {
+        $(
+            unsafe impl<$($generic)*> Allocator for $type where $($bound)* {
+                fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+                    allocate(*self, layout)
+                }
+
+                unsafe fn deallocate(&self, ptr: NonNull<u8>, _layout: Layout) {
+                    self.free(ptr.as_ptr() as *mut c_void)
+                }
+
+                unsafe fn grow(&self, ptr: NonNull<u8>, _old_layout: Layout, new_layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+                    resize(*self, ptr, new_layout)
+                }
+
+                unsafe fn grow_zeroed(&self, ptr: NonNull<u8>, old_layout: Layout, new_layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+                    let result = resize(*self, ptr, new_layout)?;
+                    let grown = new_layout.size() - old_layout.size();
+                    (result.as_ptr() as *mut u8).add(old_layout.size()).write_bytes(0, grown);
+                    Ok(result)
+                }
+
+                unsafe fn shrink(&self, ptr: NonNull<u8>, _old_layout: Layout, new_layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+                    //a zero sized realloc would free the block, which the caller still owns
+                    if new_layout.size() == 0 {
+                        return Ok(NonNull::slice_from_raw_parts(ptr, 0));
+                    }
+                    resize(*self, ptr, new_layout)
+                }
+            }
+        )*
+    };
+}
+
+allocator! {
+    ['a, const SIZE: usize, const ALIGNMENT: usize] &'a Bump<SIZE, ALIGNMENT> where [Alignment<ALIGNMENT>: Aligned];
+    ['a, 'b] &'a BumpRegion<'b> where [];
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use std::boxed::Box;
+    use std::vec::Vec;
+
+    #[test]
+    fn vec_in_bump() {
+        let bump = Bump::<1024, 8>::new();
+        let boxed = Box::new_in(7u64, &bump);
+        assert_eq!(bump.get_count(), 1);
+        {
+            let mut values = Vec::new_in(&bump);
+            for value in 0..64u32 {
+                values.push(value);
+            }
+            assert_eq!(values.iter().sum::<u32>(), 2016);
+            //every push grew the same block in place
+            assert_eq!(bump.get_count(), 2);
+            values.shrink_to_fit();
+        }
+        assert_eq!(bump.get_count(), 1);
+        assert_eq!(*boxed, 7);
+        drop(boxed);
+        assert_eq!(bump.get_count(), 0);
+        assert!(bump.get_maximum_usage() >= 256);
+    }
+
+    #[test]
+    fn shrink_to_zero_keeps_block() {
+        let bump = Bump::<256, 8>::new();
+        let kept = (&bump).allocate(Layout::new::<u64>()).unwrap();
+        let block = (&bump).allocate(Layout::new::<[u64; 2]>()).unwrap();
+        let ptr = block.cast::<u8>();
+        let shrunk = unsafe { (&bump).shrink(ptr, Layout::new::<[u64; 2]>(), Layout::from_size_align(0, 8).unwrap()) }.unwrap();
+        assert_eq!((shrunk.cast::<u8>(), shrunk.len()), (ptr, 0));
+        assert_eq!(bump.get_count(), 2);
+        unsafe { (&bump).deallocate(ptr, Layout::from_size_align(0, 8).unwrap()) };
+        assert_eq!(bump.get_count(), 1);
+        unsafe { (&bump).deallocate(kept.cast(), Layout::new::<u64>()) };
+        assert_eq!(bump.get_count(), 0);
+    }
+}
@@ -0,0 +1,626 @@
+//! Counting, block bookkeeping and notifications shared by the allocators.
+//!
+//! `Arena` holds the state, the allocator passes in the `Memory` it manages on each call.
+
+use core::cell::Cell;
+use core::ffi::c_void;
+
+use crate::observer::{DropWithoutFree, Observer};
+use crate::{Action, Marker, Reason, Status};
+
+#[derive(Copy, Clone)]
+pub(crate) struct Memory {
+    pub(crate) base: *mut u8,
+    pub(crate) size: usize,
+    pub(crate) alignment: usize,
+}
+
+/// Bookkeeping for one allocation, stored in a table growing down from the end of the heap.
+#[derive(Copy, Clone)]
+#[repr(C)]
+struct Block {
+    offset: usize,
+    size: usize,
+}
+
+//sizes never exceed isize::MAX, so the top bit is free to flag freed blocks
+const FREED: usize = 1 << (usize::BITS - 1);
+
+impl Block {
+    fn size(self: &Self) -> usize {
+        self.size & !FREED
+    }
+
+    fn is_freed(self: &Self) -> bool {
+        self.size & FREED != 0
+    }
+}
+
+pub(crate) const BLOCK_SIZE: usize = core::mem::size_of::<Block>();
+
+//what an event was about, as far as it is known
+#[derive(Copy, Clone)]
+struct Details {
+    ptr: *mut c_void,
+    requested_size: usize,
+    rounded_size: usize,
+    reason: Reason,
+}
+
+impl Details {
+    const NONE: Self = Self {
+        ptr: core::ptr::null_mut(),
+        requested_size: 0,
+        rounded_size: 0,
+        reason: Reason::None,
+    };
+
+    fn error(reason: Reason, requested_size: usize) -> Self {
+        Self { requested_size, reason, ..Self::NONE }
+    }
+}
+
+pub(crate) struct Arena {
+    pub(crate) count: Cell<usize>,
+    pub(crate) head: Cell<usize>,
+    pub(crate) blocks: Cell<usize>,
+    //entries below this index are intact, including ones left behind above `blocks` when the
+    //table was rewound, until new entries or the heap overwrite them
+    written: Cell<usize>,
+    pub(crate) maximum_usage: Cell<usize>,
+    //head and blocks at the innermost outstanding marker, memory below it is never reclaimed
+    pub(crate) floor_head: Cell<usize>,
+    pub(crate) floor_blocks: Cell<usize>,
+    pub(crate) depth: Cell<usize>,
+    pub(crate) lifo: bool,
+    pub(crate) on_drop_without_free: Cell<DropWithoutFree>,
+    observer: Cell<Observer>,
+    //set whenever an observer is registered, so one registered during a notification is kept
+    replaced: Cell<bool>,
+}
+
+impl Arena {
+    pub(crate) const fn new() -> Self {
+        Self {
+            count: Cell::new(0),
+            head: Cell::new(0),
+            blocks: Cell::new(0),
+            written: Cell::new(0),
+            maximum_usage: Cell::new(0),
+            floor_head: Cell::new(0),
+            floor_blocks: Cell::new(0),
+            depth: Cell::new(0),
+            lifo: false,
+            on_drop_without_free: Cell::new(DropWithoutFree::None),
+            observer: Cell::new(Observer::None),
+            replaced: Cell::new(false),
+        }
+    }
+
+    pub(crate) fn status(self: &Self, action: Action) -> Status {
+        Status {
+            action,
+            count: self.count.get(),
+            usage: self.usage(),
+            maximum_usage: self.maximum_usage.get(),
+            ptr: core::ptr::null_mut(),
+            requested_size: 0,
+            rounded_size: 0,
+            reason: Reason::None,
+        }
+    }
+
+    pub(crate) fn set_observer(self: &Self, observer: Observer) {
+        self.observer.set(observer);
+        self.replaced.set(true);
+    }
+
+    pub(crate) fn changed(self: &Self, action: Action){
+        self.report(action, Details::NONE)
+    }
+
+    fn report(self: &Self, action: Action, details: Details) {
+        //taken out while it runs, so an observer that allocates is not called recursively
+        let mut observer = self.observer.take();
+        if observer.is_none() {
+            return;
+        }
+        self.replaced.set(false);
+        observer.notify(Status {
+            ptr: details.ptr,
+            requested_size: details.requested_size,
+            rounded_size: details.rounded_size,
+            reason: details.reason,
+            ..self.status(action)
+        });
+        //unless it registered a replacement or removed itself
+        if !self.replaced.get() {
+            self.observer.set(observer);
+        }
+    }
+
+    fn usage(self: &Self) -> usize {
+        self.head.get() + self.blocks.get() * BLOCK_SIZE
+    }
+
+    fn update_maximum_usage(self: &Self) {
+        if self.maximum_usage.get() < self.usage() {
+            self.maximum_usage.set(self.usage());
+        }
+    }
+
+    fn rounded(memory: Memory, size: usize) -> Option<usize> {
+        //zero sized requests still get a distinct block
+        size.max(1).checked_next_multiple_of(memory.alignment)
+    }
+
+    //true if the heap can grow to `head` while the block table holds `blocks` entries
+    fn fits(memory: Memory, head: usize, blocks: usize) -> bool {
+        blocks
+            .checked_mul(BLOCK_SIZE)
+            .and_then(|table_size| memory.size.checked_sub(table_size))
+            .is_some_and(|table_start| head <= table_start)
+    }
+
+    //start and end offsets of a new block, None if the arithmetic overflows
+    fn place(self: &Self, memory: Memory, size: usize, alignment: usize) -> Option<(usize, usize)> {
+        let base = memory.base as usize;
+        let head = base.checked_add(self.head.get())?.checked_next_multiple_of(alignment)? - base;
+        Some((head, head.checked_add(Self::rounded(memory, size)?)?))
+    }
+
+    fn block_location(memory: Memory, index: usize) -> *mut Block {
+        memory.base.wrapping_add(memory.size - (index + 1) * BLOCK_SIZE) as *mut Block
+    }
+
+    fn get_block(memory: Memory, index: usize) -> Block {
+        unsafe { Self::block_location(memory, index).read_unaligned() }
+    }
+
+    fn set_block(memory: Memory, index: usize, block: Block) {
+        unsafe { Self::block_location(memory, index).write_unaligned(block) }
+    }
+
+    //moves the head up, entries left behind that it now covers are lost
+    fn raise_head(self: &Self, memory: Memory, head: usize) {
+        self.head.set(head);
+        self.written.set(self.written.get().min((memory.size - head) / BLOCK_SIZE));
+    }
+
+    //a freed block the table has been rewound past, if its entry is still intact
+    fn find_forgotten(self: &Self, memory: Memory, ptr: *mut c_void) -> Option<Block> {
+        let offset = (ptr as usize).wrapping_sub(memory.base as usize);
+        (self.blocks.get()..self.written.get())
+            .map(|index| Self::get_block(memory, index))
+            .find(|block| block.offset == offset && block.is_freed())
+    }
+
+    //blocks are recorded in address order, so the table can be searched by offset
+    fn find_block(self: &Self, memory: Memory, ptr: *mut c_void) -> Option<usize> {
+        let offset = (ptr as usize).wrapping_sub(memory.base as usize);
+        let mut low = 0;
+        let mut high = self.blocks.get();
+        while low < high {
+            let middle = low + (high - low) / 2;
+            let block = Self::get_block(memory, middle);
+            if block.offset == offset {
+                return Some(middle);
+            }
+            if block.offset < offset {
+                low = middle + 1;
+            } else {
+                high = middle;
+            }
+        }
+        None
+    }
+
+    fn allocate(self: &Self, memory: Memory, size: usize, alignment: usize) -> *mut c_void {
+        let Some((head, next_head)) = self.place(memory, size, alignment) else {
+            self.report(Action::Overflow, Details::error(Reason::Overflow, size));
+            return core::ptr::null_mut();
+        };
+        let blocks = self.blocks.get();
+        if !Self::fits(memory, next_head, blocks + 1) {
+            let details = Details { rounded_size: next_head - head, ..Details::error(Reason::OutOfMemory, size) };
+            self.report(Action::Error, details);
+            return core::ptr::null_mut();
+        }
+        let result = memory.base.wrapping_add(head) as *mut c_void;
+        Self::set_block(memory, blocks, Block { offset: head, size: next_head - head });
+        self.blocks.set(blocks + 1);
+        self.written.set(self.written.get().max(blocks + 1));
+        self.raise_head(memory, next_head);
+        self.update_maximum_usage();
+        self.count.set(self.count.get() + 1);
+        let details = Details {
+            ptr: result,
+            requested_size: size,
+            rounded_size: next_head - head,
+            reason: Reason::None,
+        };
+        self.report(Action::Malloc, details);
+        result
+    }
+
+    fn resize_in_place(self: &Self, memory: Memory, index: usize, rounded: usize) -> bool {
+        let mut block = Self::get_block(memory, index);
+        if index + 1 == self.blocks.get() && index >= self.floor_blocks.get() {
+            match block.offset.checked_add(rounded) {
+                Some(next_head) if Self::fits(memory, next_head, self.blocks.get()) => self.raise_head(memory, next_head),
+                _ => return false,
+            }
+        } else if rounded > block.size() {
+            return false;
+        }
+        block.size = rounded;
+        Self::set_block(memory, index, block);
+        self.update_maximum_usage();
+        true
+    }
+
+    pub(crate) fn malloc(self: &Self, memory: Memory, size: usize) -> *mut c_void {
+        self.allocate(memory, size, memory.alignment)
+    }
+
+    //pops freed blocks off the top of the heap
+    fn pop_freed(self: &Self, memory: Memory) {
+        let floor = self.floor_blocks.get();
+        let mut blocks = self.blocks.get();
+        while blocks > floor && Self::get_block(memory, blocks - 1).is_freed() {
+            blocks -= 1;
+        }
+        if blocks == self.blocks.get() {
+            return;
+        }
+        //the padding in front of a popped block is handed back along with it
+        let head = if blocks > floor {
+            let top = Self::get_block(memory, blocks - 1);
+            top.offset + top.size()
+        } else {
+            self.floor_head.get()
+        };
+        self.head.set(head);
+        self.blocks.set(blocks);
+    }
+
+    //rewinds to the innermost marker once nothing is in use
+    fn reset_if_unused(self: &Self) {
+        if self.count.get() == 0 {
+            self.head.set(self.floor_head.get());
+            self.blocks.set(self.floor_blocks.get());
+        }
+    }
+
+    pub(crate) unsafe fn free(self: &Self, memory: Memory, ptr: *mut c_void) {
+        if ptr.is_null() {
+            return;
+        }
+        //pointers that do not start a block are reported instead of corrupting the count
+        let Some(index) = self.find_block(memory, ptr) else {
+            //blocks freed before the table rewound are still recognized while their entry lasts
+            if cfg!(feature = "debug") {
+                if let Some(block) = self.find_forgotten(memory, ptr) {
+                    let details = Details { ptr, rounded_size: block.size(), reason: Reason::DoubleFree, ..Details::NONE };
+                    self.report(Action::DoubleFree, details);
+                    return;
+                }
+            }
+            self.report(Action::InvalidFree, Details { ptr, reason: Reason::InvalidFree, ..Details::NONE });
+            return;
+        };
+        let mut block = Self::get_block(memory, index);
+        let details = Details { ptr, rounded_size: block.size(), ..Details::NONE };
+        //a second free must not decrement the count again, the debug feature names it
+        if block.is_freed() {
+            if cfg!(feature = "debug") {
+                self.report(Action::DoubleFree, Details { reason: Reason::DoubleFree, ..details });
+            } else {
+                self.report(Action::InvalidFree, Details { reason: Reason::InvalidFree, ..details });
+            }
+            return;
+        }
+        block.size |= FREED;
+        Self::set_block(memory, index, block);
+        if self.lifo {
+            self.pop_freed(memory);
+        }
+        //if no items are used, reset the head
+        let count = self.count.get();
+        if count > 0 {
+            self.count.set(count - 1);
+            self.reset_if_unused();
+        }
+        self.report(Action::Free, details);
+    }
+
+    //forgets every block and marker
+    fn clear(self: &Self) {
+        self.count.set(0);
+        self.head.set(0);
+        self.blocks.set(0);
+        self.floor_head.set(0);
+        self.floor_blocks.set(0);
+        self.depth.set(0);
+        self.changed(Action::Reset);
+    }
+
+    pub(crate) fn reset(self: &Self) -> bool {
+        if self.count.get() > 0 {
+            self.report(Action::Error, Details::error(Reason::InUse, 0));
+            return false;
+        }
+        self.clear();
+        true
+    }
+
+    pub(crate) fn force_reset(self: &Self) -> usize {
+        let count = self.count.get();
+        if count > 0 {
+            self.on_drop_without_free.get().notify(count);
+        }
+        self.clear();
+        count
+    }
+
+    pub(crate) fn mark(self: &Self) -> Marker {
+        let marker = Marker {
+            head: self.head.get(),
+            blocks: self.blocks.get(),
+            outer_head: self.floor_head.get(),
+            outer_blocks: self.floor_blocks.get(),
+            depth: self.depth.get() + 1,
+        };
+        self.floor_head.set(marker.head);
+        self.floor_blocks.set(marker.blocks);
+        self.depth.set(marker.depth);
+        marker
+    }
+
+    pub(crate) unsafe fn release(self: &Self, memory: Memory, marker: Marker) -> bool {
+        //only the innermost marker can be released, and depth 0 is never handed out
+        if marker.depth == 0 || marker.depth != self.depth.get() || marker.head != self.floor_head.get() || marker.blocks != self.floor_blocks.get() {
+            self.report(Action::Error, Details::error(Reason::OutOfOrder, 0));
+            return false;
+        }
+        self.restore(memory, marker);
+        true
+    }
+
+    //frees everything allocated since `marker`, even if markers taken later are outstanding
+    pub(crate) unsafe fn restore(self: &Self, memory: Memory, marker: Marker) {
+        let live = (marker.blocks..self.blocks.get())
+            .filter(|index| !Self::get_block(memory, *index).is_freed())
+            .count();
+        self.count.set(self.count.get().saturating_sub(live));
+        self.head.set(marker.head);
+        self.blocks.set(marker.blocks);
+        self.floor_head.set(marker.outer_head);
+        self.floor_blocks.set(marker.outer_blocks);
+        self.depth.set(marker.depth - 1);
+        if self.lifo {
+            self.pop_freed(memory);
+        }
+        self.reset_if_unused();
+        self.changed(Action::Release);
+    }
+
+    pub(crate) unsafe fn realloc(self: &Self, memory: Memory, ptr: *mut c_void, size: usize) -> *mut c_void {
+        self.aligned_realloc(memory, ptr, size, memory.alignment)
+    }
+
+    pub(crate) unsafe fn aligned_realloc(self: &Self, memory: Memory, ptr: *mut c_void, size: usize, alignment: usize) -> *mut c_void {
+        if !alignment.is_power_of_two() {
+            self.report(Action::Error, Details { ptr, ..Details::error(Reason::InvalidAlignment, size) });
+            return core::ptr::null_mut();
+        }
+        let alignment = alignment.max(memory.alignment);
+        if ptr.is_null() {
+            return self.allocate(memory, size, alignment);
+        }
+        if size == 0 {
+            self.free(memory, ptr);
+            return core::ptr::null_mut();
+        }
+        let Some(index) = self.find_block(memory, ptr) else {
+            self.report(Action::Error, Details { ptr, ..Details::error(Reason::InvalidRealloc, size) });
+            return core::ptr::null_mut();
+        };
+        let Some(rounded) = Self::rounded(memory, size) else {
+            self.report(Action::Overflow, Details { ptr, ..Details::error(Reason::Overflow, size) });
+            return core::ptr::null_mut();
+        };
+        if (ptr as usize).is_multiple_of(alignment) && self.resize_in_place(memory, index, rounded) {
+            self.report(Action::Realloc, Details { ptr, requested_size: size, rounded_size: rounded, reason: Reason::None });
+            return ptr;
+        }
+        let old_size = Self::get_block(memory, index).size();
+        let result = self.allocate(memory, size, alignment);
+        if result.is_null() {
+            return result;
+        }
+        core::ptr::copy_nonoverlapping(ptr as *const u8, result as *mut u8, old_size.min(size));
+        self.free(memory, ptr);
+        result
+    }
+
+    pub(crate) fn calloc(self: &Self, memory: Memory, nmemb: usize, size: usize) -> *mut c_void {
+        let Some(total) = nmemb.checked_mul(size) else {
+            self.report(Action::Overflow, Details::error(Reason::Overflow, nmemb.saturating_mul(size)));
+            return core::ptr::null_mut();
+        };
+        let result = self.malloc(memory, total);
+        if !result.is_null() {
+            unsafe { core::ptr::write_bytes(result as *mut u8, 0, total) };
+        }
+        result
+    }
+
+    pub(crate) fn aligned_malloc(self: &Self, memory: Memory, size: usize, alignment: usize) -> *mut c_void {
+        if !alignment.is_power_of_two() {
+            self.report(Action::Error, Details::error(Reason::InvalidAlignment, size));
+            return core::ptr::null_mut();
+        }
+        self.allocate(memory, size, alignment.max(memory.alignment))
+    }
+}
+
+/// Frees everything allocated since `marker` when dropped, so a scope is released even if its
+/// closure panics.
+pub(crate) struct Restore<'a> {
+    pub(crate) arena: &'a Arena,
+    pub(crate) memory: Memory,
+    pub(crate) marker: Marker,
+}
+
+impl Drop for Restore<'_> {
+    fn drop(self: &mut Self) {
+        //blocks allocated in the scope are unreachable once its closure has returned or unwound
+        unsafe { self.arena.restore(self.memory, self.marker) }
+    }
+}
+
+impl Drop for Arena {
+    fn drop(self: &mut Self) {
+        if self.count.get() > 0 {
+            self.on_drop_without_free.get().notify(self.count.get());
+        }
+    }
+}
+
+/// Inherent methods and `MallocFree` for allocators built on an `Arena`. The type needs
+/// `arena` and `allocator` fields and a `memory` method returning the heap it manages.
+macro_rules! arena_allocator {
+    ([$($generic:tt)*] $type:ty where [$($bound:tt)*]) => {
+        impl<$($generic)*> $type where $($bound)* {
+            pub fn get_count(self: &Self) -> usize {
+                self.arena.count.get()
+            }
+
+            pub fn get_maximum_usage(self: &Self) -> usize {
+                self.arena.maximum_usage.get()
+            }
+
+            pub fn handle_drop_without_free(self: &mut Self, handler: $crate::OnDropWithoutFree) {
+                self.arena.on_drop_without_free.set($crate::observer::DropWithoutFree::Function(handler));
+            }
+
+            pub fn handle_on_changed(self: &mut Self, handler: fn($crate::Status)) {
+                self.arena.set_observer($crate::observer::Observer::Function(handler));
+            }
+
+            /// Registers an observer that is borrowed rather than owned.
+            ///
+            /// # Safety
+            ///
+            /// `observer` must stay valid until another observer is registered or the allocator
+            /// is dropped, and must not be accessed in any other way while the allocator calls it.
+            pub unsafe fn handle_on_changed_observer<'o>(self: &mut Self, observer: *mut (dyn $crate::AllocObserver + Send + 'o)) {
+                //the caller keeps it alive for as long as it is registered
+                let observer: *mut (dyn $crate::AllocObserver + Send) = core::mem::transmute(observer);
+                self.arena.set_observer($crate::observer::Observer::Object(observer));
+            }
+
+            #[cfg(feature = "std")]
+            pub fn handle_on_changed_boxed(self: &mut Self, handler: Box<dyn FnMut($crate::Status) + Send>) {
+                self.arena.set_observer($crate::observer::Observer::Boxed(handler));
+            }
+
+            /// When enabled, freeing the most recent block hands its memory back right away,
+            /// along with any freed blocks directly beneath it.
+            pub fn set_lifo(self: &mut Self, lifo: bool) {
+                self.arena.lifo = lifo;
+            }
+
+            /// Returns a handle that stays valid until the allocator is dropped.
+            pub fn get_handle(self: core::pin::Pin<&Self>) -> $crate::AllocatorHandle {
+                let this = self.get_ref();
+                this.allocator.set($crate::Allocator::new(self));
+                this.allocator.as_ptr()
+            }
+
+            /// Frees every block allocated since `marker` was taken, see `MallocFree::release`.
+            pub fn reset_to(self: &mut Self, marker: $crate::Marker) -> bool {
+                //borrows of typed allocations end before a mutable borrow
+                unsafe { $crate::MallocFree::release(self, marker) }
+            }
+
+            /// Rewinds the heap to its start and forgets outstanding markers. Returns `false`
+            /// and reports `Action::Error` if any allocation is still in use.
+            pub fn reset(self: &mut Self) -> bool {
+                self.arena.reset()
+            }
+
+            /// Rewinds the heap even if allocations are still in use and returns how many there
+            /// were. The drop-without-free handler is called with that number if it is not zero.
+            pub fn force_reset(self: &mut Self) -> usize {
+                self.arena.force_reset()
+            }
+
+            /// Runs `f` and frees everything it allocated through `scoped` once it returns.
+            pub fn scope<R>(self: &mut Self, f: impl FnOnce(core::pin::Pin<&$crate::Scope<'_, Self>>) -> R) -> R {
+                let _restore = $crate::arena::Restore {
+                    arena: &self.arena,
+                    memory: self.memory(),
+                    marker: self.arena.mark(),
+                };
+                f(core::pin::pin!($crate::Scope::new(self)).as_ref())
+            }
+        }
+
+        //the heap is owned by the allocator and observers are `Send`. The cached handle points back
+        //at the allocator, but it is only handed out by `get_handle` once the allocator is pinned,
+        //and a pinned allocator is never moved again
+        unsafe impl<$($generic)*> Send for $type where $($bound)* {}
+
+        //blocks are carved out of `memory()` at the requested alignment and recorded in the
+        //block table, so live ones never overlap
+        unsafe impl<$($generic)*> $crate::MallocFree for $type where $($bound)* {
+            fn malloc(self: &Self, size: usize) -> *mut core::ffi::c_void {
+                self.arena.malloc(self.memory(), size)
+            }
+
+            unsafe fn free(self: &Self, ptr: *mut core::ffi::c_void) {
+                self.arena.free(self.memory(), ptr)
+            }
+
+            unsafe fn realloc(self: &Self, ptr: *mut core::ffi::c_void, size: usize) -> *mut core::ffi::c_void {
+                self.arena.realloc(self.memory(), ptr, size)
+            }
+
+            fn get_status(self: &Self) -> $crate::Status {
+                self.arena.status($crate::Action::Query)
+            }
+
+            fn calloc(self: &Self, nmemb: usize, size: usize) -> *mut core::ffi::c_void {
+                self.arena.calloc(self.memory(), nmemb, size)
+            }
+
+            fn aligned_malloc(self: &Self, size: usize, alignment: usize) -> *mut core::ffi::c_void {
+                self.arena.aligned_malloc(self.memory(), size, alignment)
+            }
+
+            unsafe fn aligned_realloc(self: &Self, ptr: *mut core::ffi::c_void, size: usize, alignment: usize) -> *mut core::ffi::c_void {
+                self.arena.aligned_realloc(self.memory(), ptr, size, alignment)
+            }
+
+            fn mark(self: &Self) -> $crate::Marker {
+                self.arena.mark()
+            }
+
+            unsafe fn release(self: &Self, marker: $crate::Marker) -> bool {
+                self.arena.release(self.memory(), marker)
+            }
+
+            unsafe fn handle_on_changed_c(self: &Self, observer: Option<$crate::OnChangedC>, context: *mut core::ffi::c_void) {
+                self.arena.set_observer($crate::observer::Observer::from_c(observer, context));
+            }
+
+            unsafe fn handle_drop_without_free_c(self: &Self, handler: Option<$crate::OnDropWithoutFreeC>, context: *mut core::ffi::c_void) {
+                self.arena.on_drop_without_free.set($crate::observer::DropWithoutFree::from_c(handler, context));
+            }
+        }
+    };
+}
+
+pub(crate) use arena_allocator;
@@ -0,0 +1,3 @@
+fn main() {
+    print!("{}", bump_malloc_free::header::generate());
+}
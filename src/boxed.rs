@@ -0,0 +1,64 @@
+use core::ffi::c_void;
+use core::mem::{align_of, size_of};
+use core::ops::{Deref, DerefMut};
+use core::ptr::NonNull;
+
+use crate::MallocFree;
+
+/// Owns a value in a `MallocFree` heap and frees its block on drop.
+pub struct BumpBox<'a, T, M: MallocFree> {
+    ptr: NonNull<T>,
+    malloc_free: &'a M,
+}
+
+impl<'a, T, M: MallocFree> BumpBox<'a, T, M> {
+    /// Returns `None` if the heap is full.
+    pub fn new_in(value: T, malloc_free: &'a M) -> Option<Self> {
+        let ptr = NonNull::new(malloc_free.aligned_malloc(size_of::<T>(), align_of::<T>()) as *mut T)?;
+        unsafe { ptr.as_ptr().write(value) };
+        Some(Self { ptr, malloc_free })
+    }
+}
+
+impl<T, M: MallocFree> Deref for BumpBox<'_, T, M> {
+    type Target = T;
+
+    fn deref(self: &Self) -> &T {
+        unsafe { self.ptr.as_ref() }
+    }
+}
+
+impl<T, M: MallocFree> DerefMut for BumpBox<'_, T, M> {
+    fn deref_mut(self: &mut Self) -> &mut T {
+        unsafe { self.ptr.as_mut() }
+    }
+}
+
+impl<T, M: MallocFree> Drop for BumpBox<'_, T, M> {
+    fn drop(self: &mut Self) {
+        unsafe {
+            core::ptr::drop_in_place(self.ptr.as_ptr());
+            self.malloc_free.free(self.ptr.as_ptr() as *mut c_void);
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::Bump;
+
+    #[test]
+    fn box_frees_on_drop() {
+        let bump = Bump::<256, 8>::new();
+        let mut first = BumpBox::new_in(String::from("first"), &bump).unwrap();
+        first.push('!');
+        let second = BumpBox::new_in(7u16, &bump).unwrap();
+        assert_eq!((first.as_str(), *second), ("first!", 7));
+        assert_eq!(bump.get_count(), 2);
+        drop(first);
+        drop(second);
+        assert_eq!(bump.get_count(), 0);
+        assert!(BumpBox::new_in([0u8; 256], &bump).is_none());
+    }
+}
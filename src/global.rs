@@ -0,0 +1,157 @@
+use core::alloc::{GlobalAlloc, Layout};
+use core::ffi::c_void;
+use core::marker::PhantomData;
+use core::pin::Pin;
+
+use crate::MallocFree;
+
+/// Runs each call of the global allocator, for example with interrupts disabled.
+pub trait CriticalSection {
+    fn with<R>(f: impl FnOnce() -> R) -> R;
+}
+
+/// Runs calls as they come, for firmware where only one context ever allocates.
+pub struct Unsynchronized;
+
+impl CriticalSection for Unsynchronized {
+    fn with<R>(f: impl FnOnce() -> R) -> R {
+        f()
+    }
+}
+
+/// Serves a `MallocFree` as the `#[global_allocator]`.
+///
+/// Allocator state is not synchronized by itself. `alloc`, `dealloc` and `realloc` run inside
+/// `C::with`, so an interrupt handler may allocate as long as `C` masks interrupts.
+///
+/// ```no_run
+/// use bump_malloc_free::{Bump, CriticalSection, GlobalBump};
+///
+/// struct Interrupts;
+///
+/// impl CriticalSection for Interrupts {
+///     fn with<R>(f: impl FnOnce() -> R) -> R {
+///         //e.g. cortex_m::interrupt::free(|_| f())
+///         f()
+///     }
+/// }
+///
+/// #[global_allocator]
+/// static HEAP: GlobalBump<Bump<65536, 8>, Interrupts> = unsafe { GlobalBump::new(Bump::new()) };
+///
+/// //C libraries allocate from the same budget
+/// let handle = HEAP.get().get_handle();
+/// ```
+pub struct GlobalBump<M, C = Unsynchronized> {
+    malloc_free: M,
+    _critical_section: PhantomData<C>,
+}
+
+unsafe impl<M: Send, C> Sync for GlobalBump<M, C> {}
+
+impl<M: MallocFree, C: CriticalSection> GlobalBump<M, C> {
+    /// # Safety
+    ///
+    /// `C::with` must keep its calls from overlapping, including with interrupt handlers that
+    /// allocate; `Unsynchronized` only does so if a single core and no interrupt handler
+    /// allocates. Calls made through `get` must not overlap them either.
+    pub const unsafe fn new(malloc_free: M) -> Self {
+        Self {
+            malloc_free,
+            _critical_section: PhantomData,
+        }
+    }
+
+    pub fn get(self: &'static Self) -> Pin<&'static M> {
+        Pin::static_ref(&self.malloc_free)
+    }
+}
+
+unsafe impl<M: MallocFree, C: CriticalSection> GlobalAlloc for GlobalBump<M, C> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        C::with(|| self.malloc_free.aligned_malloc(layout.size(), layout.align()) as *mut u8)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, _layout: Layout) {
+        C::with(|| self.malloc_free.free(ptr as *mut c_void))
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        let result = self.alloc(layout);
+        if !result.is_null() {
+            core::ptr::write_bytes(result, 0, layout.size());
+        }
+        result
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        C::with(|| self.malloc_free.aligned_realloc(ptr as *mut c_void, new_size, layout.align()) as *mut u8)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::Bump;
+
+    #[test]
+    fn global_alloc_honors_layout() {
+        let global: GlobalBump<_> = unsafe { GlobalBump::new(Bump::<1024, 8>::new()) };
+        unsafe {
+            let small = global.alloc(Layout::from_size_align(3, 1).unwrap());
+            let aligned = global.alloc(Layout::from_size_align(16, 64).unwrap());
+            assert_eq!(aligned as usize % 64, 0);
+
+            let zeroed = global.alloc_zeroed(Layout::from_size_align(32, 8).unwrap());
+            assert_eq!(core::slice::from_raw_parts(zeroed, 32), &[0; 32]);
+            zeroed.write_bytes(7, 32);
+            //the most recent block grows in place
+            let grown = global.realloc(zeroed, Layout::from_size_align(32, 8).unwrap(), 64);
+            assert_eq!(grown, zeroed);
+
+            aligned.write_bytes(9, 16);
+            let moved = global.realloc(aligned, Layout::from_size_align(16, 64).unwrap(), 128);
+            assert_eq!(moved as usize % 64, 0);
+            assert_eq!(core::slice::from_raw_parts(moved, 16), &[9; 16]);
+
+            for ptr in [small, grown, moved] {
+                global.dealloc(ptr, Layout::new::<u8>());
+            }
+        }
+        assert_eq!(global.malloc_free.get_count(), 0);
+    }
+
+    #[test]
+    fn global_alloc_runs_in_critical_section() {
+        static LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
+        struct Locked;
+
+        impl CriticalSection for Locked {
+            fn with<R>(f: impl FnOnce() -> R) -> R {
+                let _guard = LOCK.lock().unwrap();
+                f()
+            }
+        }
+
+        static GLOBAL: GlobalBump<Bump<4096, 8>, Locked> = unsafe { GlobalBump::new(Bump::new()) };
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                std::thread::spawn(|| {
+                    for size in 1..50 {
+                        unsafe {
+                            let layout = Layout::from_size_align(size, 8).unwrap();
+                            let ptr = GLOBAL.alloc(layout);
+                            assert!(!ptr.is_null());
+                            ptr.write_bytes(1, size);
+                            GLOBAL.dealloc(ptr, layout);
+                        }
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(GLOBAL.malloc_free.get_count(), 0);
+    }
+}
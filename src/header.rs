@@ -0,0 +1,337 @@
+//! Generates `include/bump_malloc_free.h` from the Rust definitions of the C ABI.
+//!
+//! Function signatures are taken from the exported functions themselves and struct
+//! fields from exhaustive patterns, so the header cannot drift without failing to build.
+
+use core::any::TypeId;
+
+use crate::*;
+
+trait CType {
+    fn declare(declarator: &str) -> String;
+
+    //spelled out even if the type has a typedef, to declare that typedef
+    fn declare_unnamed(declarator: &str) -> String {
+        Self::declare(declarator)
+    }
+
+    fn is_callback() -> bool {
+        false
+    }
+}
+
+trait CFunction {
+    fn declare(declarator: &str, parameters: &[&str]) -> String;
+}
+
+fn declare_named(name: &str, declarator: &str) -> String {
+    if declarator.is_empty() {
+        name.to_string()
+    } else {
+        format!("{} {}", name, declarator)
+    }
+}
+
+macro_rules! named_type {
+    ($type:ty, $name:literal) => {
+        impl CType for $type {
+            fn declare(declarator: &str) -> String {
+                declare_named($name, declarator)
+            }
+        }
+    };
+}
+
+named_type!((), "void");
+named_type!(c_void, "void");
+named_type!(usize, "size_t");
+named_type!(c_int, "int");
+named_type!(Action, "bump_action_t");
+named_type!(Reason, "bump_reason_t");
+named_type!(Status, "bump_status_t");
+named_type!(Marker, "bump_marker_t");
+named_type!(Allocator, "bump_allocator_t");
+
+impl<T: CType> CType for *mut T {
+    fn declare(declarator: &str) -> String {
+        T::declare(&format!("*{}", declarator))
+    }
+}
+
+//nullable function pointers
+impl<T: CType> CType for Option<T> {
+    fn declare(declarator: &str) -> String {
+        T::declare(declarator)
+    }
+
+    fn is_callback() -> bool {
+        T::is_callback()
+    }
+}
+
+impl<T: CType> CType for *const T {
+    fn declare(declarator: &str) -> String {
+        format!("const {}", T::declare(&format!("*{}", declarator)))
+    }
+}
+
+//callbacks C code implements itself are declared through a typedef
+fn callback_name<F: 'static>() -> Option<&'static str> {
+    let id = TypeId::of::<F>();
+    if id == TypeId::of::<OnChangedC>() {
+        Some("bump_on_changed_c_t")
+    } else if id == TypeId::of::<OnDropWithoutFreeC>() {
+        Some("bump_on_drop_without_free_c_t")
+    } else {
+        None
+    }
+}
+
+macro_rules! function_type {
+    ($($parameter:ident),*) => {
+        impl<R: CType, $($parameter: CType),*> CFunction for unsafe extern "C" fn($($parameter),*) -> R {
+            #[allow(unused_variables, unused_mut)]
+            fn declare(declarator: &str, parameters: &[&str]) -> String {
+                let mut names = parameters.iter();
+                let declared: &[String] = &[$($parameter::declare(names.next().unwrap())),*];
+                assert!(names.next().is_none(), "too many parameter names for {}", declarator);
+                R::declare(&format!("{}({})", declarator, declared.join(", ")))
+            }
+        }
+
+        impl<R: CType + 'static, $($parameter: CType + 'static),*> CType for unsafe extern "C" fn($($parameter),*) -> R {
+            fn declare(declarator: &str) -> String {
+                match callback_name::<Self>() {
+                    Some(name) => declare_named(name, declarator),
+                    None => Self::declare_unnamed(declarator),
+                }
+            }
+
+            fn declare_unnamed(declarator: &str) -> String {
+                let unnamed: &[&str] = &[$(function_type!(@unnamed $parameter)),*];
+                <Self as CFunction>::declare(&format!("(*{})", declarator), unnamed)
+            }
+
+            fn is_callback() -> bool {
+                true
+            }
+        }
+    };
+    (@unnamed $parameter:ident) => {
+        ""
+    };
+}
+
+function_type!(A);
+function_type!(A, B);
+function_type!(A, B, C);
+function_type!(A, B, C, D);
+
+struct Field {
+    offset: usize,
+    name: &'static str,
+    declare: fn(&str) -> String,
+    is_callback: bool,
+}
+
+fn field<T: CType>(_: &T, offset: usize, name: &'static str) -> Field {
+    Field {
+        offset,
+        name,
+        declare: T::declare,
+        is_callback: T::is_callback(),
+    }
+}
+
+macro_rules! fields {
+    ($value:expr, $type:ident { $($field:ident),* }) => {{
+        let $type { $($field),* } = $value;
+        let mut fields = vec![$(field(&$field, core::mem::offset_of!($type, $field), stringify!($field))),*];
+        fields.sort_by_key(|field| field.offset);
+        fields
+    }};
+}
+
+fn declare_function<F: CFunction>(_: F, name: &str, parameters: &[&str]) -> String {
+    F::declare(name, parameters)
+}
+
+macro_rules! function {
+    ($function:ident as $type:ty, [$($parameter:ident),*]) => {
+        declare_function($function as $type, stringify!($function), &[$(stringify!($parameter)),*])
+    };
+}
+
+fn upper_snake(name: &str) -> String {
+    let mut result = String::new();
+    for (index, character) in name.chars().enumerate() {
+        if index > 0 && character.is_uppercase() {
+            result.push('_');
+        }
+        result.push(character.to_ascii_uppercase());
+    }
+    result
+}
+
+fn actions() -> Vec<Action> {
+    //a new variant fails to build here until it is added to the list
+    match Action::Free {
+        Action::Free | Action::Malloc | Action::Error | Action::Query | Action::Realloc | Action::Overflow | Action::Release | Action::Reset | Action::InvalidFree | Action::DoubleFree => {}
+    }
+    vec![Action::Free, Action::Malloc, Action::Error, Action::Query, Action::Realloc, Action::Overflow, Action::Release, Action::Reset, Action::InvalidFree, Action::DoubleFree]
+}
+
+fn reasons() -> Vec<Reason> {
+    match Reason::None {
+        Reason::None
+        | Reason::OutOfMemory
+        | Reason::Overflow
+        | Reason::InvalidAlignment
+        | Reason::InvalidFree
+        | Reason::InvalidRealloc
+        | Reason::DoubleFree
+        | Reason::OutOfOrder
+        | Reason::InUse => {}
+    }
+    vec![
+        Reason::None,
+        Reason::OutOfMemory,
+        Reason::Overflow,
+        Reason::InvalidAlignment,
+        Reason::InvalidFree,
+        Reason::InvalidRealloc,
+        Reason::DoubleFree,
+        Reason::OutOfOrder,
+        Reason::InUse,
+    ]
+}
+
+fn declare_enum<T: core::fmt::Debug + Copy>(output: &mut String, name: &str, variants: Vec<T>, value: fn(T) -> i32) {
+    let prefix = name.trim_end_matches("_t").to_uppercase();
+    output.push_str("typedef enum {\n");
+    for variant in variants {
+        let variant_name = upper_snake(&format!("{:?}", variant));
+        output.push_str(&format!("    {}_{} = {},\n", prefix, variant_name, value(variant)));
+    }
+    output.push_str(&format!("}} {};\n\n", name));
+}
+
+fn declare_struct(output: &mut String, name: &str, fields: Vec<Field>) {
+    for field in fields.iter().filter(|field| field.is_callback) {
+        let callback = format!("bump_{}_fn_t", field.name);
+        output.push_str(&format!("typedef {};\n", (field.declare)(&callback)));
+    }
+    if fields.iter().any(|field| field.is_callback) {
+        output.push('\n');
+    }
+    output.push_str("typedef struct {\n");
+    for field in &fields {
+        if field.is_callback {
+            output.push_str(&format!("    bump_{}_fn_t {};\n", field.name, field.name));
+        } else {
+            output.push_str(&format!("    {};\n", (field.declare)(field.name)));
+        }
+    }
+    output.push_str(&format!("}} {};\n\n", name));
+}
+
+fn declare_callback<F: CType + 'static>(output: &mut String) {
+    let name = callback_name::<F>().expect("callback without a typedef name");
+    output.push_str(&format!("typedef {};\n", F::declare_unnamed(name)));
+}
+
+fn declare_functions(output: &mut String) {
+    let functions = [
+        function!(bump_malloc as unsafe extern "C" fn(_, _) -> _, [handle, size]),
+        function!(bump_free as unsafe extern "C" fn(_, _) -> _, [handle, ptr]),
+        function!(bump_calloc as unsafe extern "C" fn(_, _, _) -> _, [handle, nmemb, size]),
+        function!(bump_realloc as unsafe extern "C" fn(_, _, _) -> _, [handle, ptr, size]),
+        function!(bump_aligned_alloc as unsafe extern "C" fn(_, _, _) -> _, [handle, alignment, size]),
+        function!(bump_posix_memalign as unsafe extern "C" fn(_, _, _, _) -> _, [handle, memptr, alignment, size]),
+        function!(bump_status as unsafe extern "C" fn(_) -> _, [handle]),
+        function!(bump_mark as unsafe extern "C" fn(_) -> _, [handle]),
+        function!(bump_release as unsafe extern "C" fn(_, _) -> _, [handle, marker]),
+        function!(bump_handle_on_changed as unsafe extern "C" fn(_, _, _) -> _, [handle, observer, context]),
+        function!(bump_handle_drop_without_free as unsafe extern "C" fn(_, _, _) -> _, [handle, handler, context]),
+        function!(bump_create as unsafe extern "C" fn(_, _, _) -> _, [buffer, size, alignment]),
+        function!(bump_destroy as unsafe extern "C" fn(_) -> _, [handle]),
+    ];
+    for function in functions {
+        output.push_str(&format!("{};\n", function));
+    }
+}
+
+pub fn generate() -> String {
+    let bump = Bump::<1, 1>::new();
+    let allocator = Allocator::from_context::<Bump<1, 1>>(core::ptr::null_mut());
+
+    let mut output = String::new();
+    output.push_str(
+        "/* Generated by `cargo run --features std --bin generate-header`, do not edit. */\n\n\
+         #ifndef BUMP_MALLOC_FREE_H\n\
+         #define BUMP_MALLOC_FREE_H\n\n\
+         #include <stddef.h>\n\n\
+         #ifdef __cplusplus\n\
+         extern \"C\" {\n\
+         #endif\n\n",
+    );
+    declare_enum(&mut output, "bump_action_t", actions(), |action| action as i32);
+    declare_enum(&mut output, "bump_reason_t", reasons(), |reason| reason as i32);
+    declare_struct(
+        &mut output,
+        "bump_status_t",
+        fields!(bump.get_status(), Status { action, count, usage, maximum_usage, ptr, requested_size, rounded_size, reason }),
+    );
+    declare_struct(
+        &mut output,
+        "bump_marker_t",
+        fields!(bump.mark(), Marker { head, blocks, outer_head, outer_blocks, depth }),
+    );
+    declare_callback::<OnChangedC>(&mut output);
+    declare_callback::<OnDropWithoutFreeC>(&mut output);
+    output.push('\n');
+    declare_struct(
+        &mut output,
+        "bump_allocator_t",
+        fields!(allocator, Allocator { context, malloc, free, realloc, status, calloc, aligned_malloc, mark, release, on_changed, on_drop_without_free }),
+    );
+    declare_functions(&mut output);
+    output.push_str(
+        "\n#ifdef __cplusplus\n\
+         }\n\
+         #endif\n\n\
+         #endif /* BUMP_MALLOC_FREE_H */\n",
+    );
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header_is_up_to_date() {
+        assert_eq!(generate(), include_str!("../include/bump_malloc_free.h"));
+    }
+
+    #[test]
+    fn header_declares_every_export() {
+        let header = generate();
+        for entry in std::fs::read_dir("src").unwrap() {
+            let path = entry.unwrap().path();
+            if !path.is_file() {
+                continue;
+            }
+            let source = std::fs::read_to_string(path).unwrap();
+            let mut lines = source.lines();
+            while let Some(line) = lines.next() {
+                if line.trim() != "#[no_mangle]" {
+                    continue;
+                }
+                let signature = lines.next().unwrap();
+                let name = signature.split("fn ").nth(1).unwrap().split('(').next().unwrap();
+                assert!(header.contains(&format!("{}(", name)), "{} is missing from the header", name);
+            }
+        }
+    }
+}
@@ -1,150 +1,603 @@
 #![cfg_attr(not(feature = "std"), no_std)]
+#![cfg_attr(feature = "allocator_api", feature(allocator_api))]
+#![allow(clippy::needless_arbitrary_self_type)]
 
-use core::ffi::c_void;
+use core::cell::{Cell, UnsafeCell};
+use core::ffi::{c_int, c_void};
+use core::marker::PhantomPinned;
+use core::mem::{align_of, size_of, size_of_val, MaybeUninit};
+use core::pin::Pin;
 
+mod alignment;
+#[cfg(feature = "allocator_api")]
+mod allocator_api;
+mod arena;
+mod boxed;
+mod global;
+#[cfg(feature = "std")]
+#[doc(hidden)]
+pub mod header;
+mod observer;
+mod region;
+mod scope;
+mod vec;
+
+pub use alignment::{Aligned, Alignment};
+pub use boxed::BumpBox;
+pub use global::{CriticalSection, GlobalBump, Unsynchronized};
+pub use observer::{AllocObserver, OnChangedC, OnDropWithoutFreeC};
+pub use region::{bump_create, bump_destroy, BumpRegion};
+pub use scope::Scope;
+pub use vec::BumpVec;
+
+use arena::{arena_allocator, Arena, Memory};
+
+/// Function table C code can call directly to reach a `MallocFree` implementation.
+///
+/// `context` is passed as the first argument of every entry. The target must stay pinned
+/// and alive for as long as the `Allocator` is in use.
 #[derive(Copy, Clone)]
 #[repr(C)]
 pub struct Allocator {
-    _container: [u8; core::mem::size_of::<&mut dyn MallocFree>()],
+    pub context: *mut c_void,
+    pub malloc: unsafe extern "C" fn(*mut c_void, usize) -> *mut c_void,
+    pub free: unsafe extern "C" fn(*mut c_void, *mut c_void),
+    pub realloc: unsafe extern "C" fn(*mut c_void, *mut c_void, usize) -> *mut c_void,
+    pub status: unsafe extern "C" fn(*mut c_void) -> Status,
+    pub calloc: unsafe extern "C" fn(*mut c_void, usize, usize) -> *mut c_void,
+    pub aligned_malloc: unsafe extern "C" fn(*mut c_void, usize, usize) -> *mut c_void,
+    pub mark: unsafe extern "C" fn(*mut c_void) -> Marker,
+    pub release: unsafe extern "C" fn(*mut c_void, Marker) -> c_int,
+    pub on_changed: unsafe extern "C" fn(*mut c_void, Option<OnChangedC>, *mut c_void),
+    pub on_drop_without_free: unsafe extern "C" fn(*mut c_void, Option<OnDropWithoutFreeC>, *mut c_void),
 }
 
 pub type AllocatorHandle = *const Allocator;
 
-pub type OnDropWithoutFree = fn();
+/// Receives the number of allocations that were never freed.
+pub type OnDropWithoutFree = fn(usize);
 
-#[derive(Debug)]
+/// What an event reports.
+///
+/// `Overflow`, `InvalidFree` and `DoubleFree` are failures that kept an action of their own
+/// when they were introduced, so existing `on_changed` handlers can tell them apart by action.
+/// Every other failure is an `Error`. All failures also set `Status::reason`, which repeats the
+/// dedicated actions so new handlers can switch on the reason alone.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(C)]
 pub enum Action {
     Free,
     Malloc,
     Error,
+    Query,
+    Realloc,
+    Overflow,
+    Release,
+    Reset,
+    InvalidFree,
+    DoubleFree,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
 pub struct Status {
     pub action: Action,
     pub count: usize,
+    /// Bytes in use, including alignment padding and one block table entry per allocation.
     pub usage: usize,
+    /// Highest `usage` so far.
     pub maximum_usage: usize,
+    /// Block the event is about, null if there is none.
+    pub ptr: *mut c_void,
+    /// Size passed by the caller, saturated if computing it overflowed.
+    pub requested_size: usize,
+    /// Size of the block in the heap, zero if unknown.
+    pub rounded_size: usize,
+    pub reason: Reason,
 }
 
-fn no_panic_on_drop_without_free() {}
-
-pub trait MallocFree {
-    fn malloc(self: &mut Self, size: usize) -> *mut c_void;
-    fn free(self: &mut Self, _ptr: *mut c_void);
-    fn get_allocator(self: &mut Self) -> Allocator;
+/// Why an `Error`, `Overflow`, `InvalidFree` or `DoubleFree` happened, `None` for every other
+/// action. See `Action` for why some reasons repeat an action.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(C)]
+pub enum Reason {
+    None,
+    OutOfMemory,
+    Overflow,
+    InvalidAlignment,
+    InvalidFree,
+    InvalidRealloc,
+    DoubleFree,
+    /// A marker was released while one taken after it was still outstanding.
+    OutOfOrder,
+    /// `reset` was called while allocations were still in use.
+    InUse,
 }
 
-pub struct Bump<const SIZE: usize, const ALIGNMENT: usize> {
-    count: usize,
-    head: usize,
-    pub heap: [u8; SIZE],
-    maximum_usage: usize,
-    on_drop_without_free: OnDropWithoutFree,
-    on_changed: Option<fn(Status)>,
+/// Position of the heap returned by `mark`, releasing it frees every block allocated since.
+///
+/// Markers must be released in the reverse order they were taken.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[repr(C)]
+pub struct Marker {
+    pub(crate) head: usize,
+    pub(crate) blocks: usize,
+    pub(crate) outer_head: usize,
+    pub(crate) outer_blocks: usize,
+    pub(crate) depth: usize,
 }
 
-impl<const SIZE: usize, const ALIGNMENT: usize> Bump<SIZE, ALIGNMENT> {
-    pub fn new() -> Self {
-        Self {
-            count: 0,
+/// Heap that hands out and takes back raw blocks, reachable from C through `Allocator`.
+///
+/// # Safety
+///
+/// A non-null block returned by `malloc`, `realloc`, `calloc`, `aligned_malloc` or
+/// `aligned_realloc` must be valid for reads and writes of the requested size, aligned to the
+/// requested alignment (at least the allocator's own) and must not overlap any other live block
+/// until it is freed, reallocated or released. `BumpBox` and `BumpVec` write through such blocks
+/// from safe code.
+pub unsafe trait MallocFree {
+    fn malloc(self: &Self, size: usize) -> *mut c_void;
+    /// # Safety
+    ///
+    /// `ptr` must be a live allocation returned by this allocator.
+    unsafe fn free(self: &Self, ptr: *mut c_void);
+    /// # Safety
+    ///
+    /// `ptr` must be null or a live allocation returned by this allocator.
+    unsafe fn realloc(self: &Self, ptr: *mut c_void, size: usize) -> *mut c_void;
+    fn get_status(self: &Self) -> Status;
+    /// Returns null if `nmemb * size` overflows.
+    fn calloc(self: &Self, nmemb: usize, size: usize) -> *mut c_void {
+        let Some(total) = nmemb.checked_mul(size) else {
+            return core::ptr::null_mut();
+        };
+        let result = self.malloc(total);
+        if !result.is_null() {
+            unsafe { core::ptr::write_bytes(result as *mut u8, 0, total) };
+        }
+        result
+    }
+    /// Allocates `size` bytes aligned to `alignment`, which must be a power of two.
+    fn aligned_malloc(self: &Self, size: usize, alignment: usize) -> *mut c_void;
+    /// Like `realloc`, but a moved block is aligned to `alignment`.
+    ///
+    /// The default always moves the block, since only `realloc` knows its old size.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must be null or a live allocation returned by this allocator.
+    unsafe fn aligned_realloc(self: &Self, ptr: *mut c_void, size: usize, alignment: usize) -> *mut c_void {
+        if ptr.is_null() {
+            return self.aligned_malloc(size, alignment);
+        }
+        if size == 0 {
+            self.free(ptr);
+            return core::ptr::null_mut();
+        }
+        //allocated first, so `ptr` is untouched if the heap is full
+        let result = self.aligned_malloc(size, alignment);
+        if result.is_null() {
+            return result;
+        }
+        let moved = self.realloc(ptr, size);
+        if moved.is_null() {
+            self.free(result);
+            return moved;
+        }
+        core::ptr::copy_nonoverlapping(moved as *const u8, result as *mut u8, size);
+        self.free(moved);
+        result
+    }
+    /// Memory below the returned marker is not reclaimed until it is released.
+    ///
+    /// Allocators without markers keep the default, whose markers `release` always rejects.
+    fn mark(self: &Self) -> Marker {
+        Marker {
             head: 0,
-            heap: [0; SIZE],
-            maximum_usage: 0,
-            on_drop_without_free: no_panic_on_drop_without_free,
-            on_changed: None,
+            blocks: 0,
+            outer_head: 0,
+            outer_blocks: 0,
+            depth: 0,
         }
     }
-
-    pub fn get_count(self: &Self) -> usize {
-        self.count
+    /// Frees every block allocated since `marker` was taken. Returns `false` and changes
+    /// nothing if a marker taken later has not been released yet.
+    ///
+    /// # Safety
+    ///
+    /// Blocks allocated since `marker` must not be used afterwards.
+    unsafe fn release(self: &Self, _marker: Marker) -> bool {
+        false
     }
+    /// Replaces the observer with `observer`, called with `context` on every change. `None`
+    /// removes it. Allocators that do not report changes keep the default, which ignores it.
+    ///
+    /// # Safety
+    ///
+    /// `observer` must be safe to call with `context`, and `context` must stay valid for as
+    /// long as the observer is registered, on whichever thread the allocator is used from.
+    unsafe fn handle_on_changed_c(self: &Self, _observer: Option<OnChangedC>, _context: *mut c_void) {}
+    /// Replaces the drop-without-free handler with `handler`, called with `context` and the
+    /// number of allocations still in use when the allocator is dropped or force reset. `None`
+    /// removes it. Allocators that do not track leaks keep the default, which ignores it.
+    ///
+    /// # Safety
+    ///
+    /// `handler` must be safe to call with `context`, and `context` must stay valid for as long
+    /// as the handler is registered, on whichever thread the allocator is used from.
+    unsafe fn handle_drop_without_free_c(self: &Self, _handler: Option<OnDropWithoutFreeC>, _context: *mut c_void) {}
+}
+
+/// Backing storage of a `Bump`, aligned to `ALIGNMENT`.
+#[repr(C)]
+pub struct Heap<const SIZE: usize, const ALIGNMENT: usize>
+where
+    Alignment<ALIGNMENT>: Aligned,
+{
+    _alignment: [<Alignment<ALIGNMENT> as Aligned>::Archetype; 0],
+    bytes: UnsafeCell<[u8; SIZE]>,
+}
 
-    pub fn get_maximum_usage(self: &Self) -> usize {
-        self.maximum_usage
+impl<const SIZE: usize, const ALIGNMENT: usize> Heap<SIZE, ALIGNMENT>
+where
+    Alignment<ALIGNMENT>: Aligned,
+{
+    const fn new() -> Self {
+        Self {
+            _alignment: [],
+            bytes: UnsafeCell::new([0; SIZE]),
+        }
     }
 
-    pub fn handle_drop_without_free(self: &mut Self, handler: OnDropWithoutFree) {
-        self.on_drop_without_free = handler;
+    pub fn as_ptr(self: &Self) -> *mut u8 {
+        self.bytes.get() as *mut u8
     }
+}
 
-    pub fn handle_on_changed(self: &mut Self, handler: fn(Status)) {
-        self.on_changed = Some(handler);
+/// Bump allocator over a `SIZE` byte heap stored inline and aligned to `ALIGNMENT`.
+///
+/// Besides its size rounded up to `ALIGNMENT`, every allocation takes a two-word entry
+/// (16 bytes on 64-bit targets, 8 on 32-bit) in a block table kept at the end of the heap, so
+/// `free` and `realloc` can find it. Size `SIZE` for both: a `Bump<1024, 8>` holds 42 blocks of
+/// 8 bytes on a 64-bit target, and `malloc(1024)` does not fit.
+///
+/// A marker frees everything allocated since it was taken:
+///
+/// ```
+/// use bump_malloc_free::{Bump, MallocFree};
+///
+/// let mut bump = Bump::<1024, 8>::new();
+/// let marker = bump.mark();
+/// //a C library that never frees
+/// bump.malloc(64);
+/// assert!(bump.reset_to(marker));
+/// assert_eq!(bump.get_count(), 0);
+/// ```
+///
+/// and so does a scope once its closure returns:
+///
+/// ```
+/// use bump_malloc_free::{Bump, MallocFree};
+///
+/// let mut bump = Bump::<1024, 8>::new();
+/// for _frame in 0..3 {
+///     bump.scope(|scoped| {
+///         let _handle = scoped.get_handle();
+///         //hand `handle` to a C library
+///     });
+/// }
+/// assert_eq!(bump.get_count(), 0);
+/// ```
+pub struct Bump<const SIZE: usize, const ALIGNMENT: usize>
+where
+    Alignment<ALIGNMENT>: Aligned,
+{
+    arena: Arena,
+    pub heap: Heap<SIZE, ALIGNMENT>,
+    allocator: Cell<Allocator>,
+    _pinned: PhantomPinned,
+}
+
+impl<const SIZE: usize, const ALIGNMENT: usize> Default for Bump<SIZE, ALIGNMENT>
+where
+    Alignment<ALIGNMENT>: Aligned,
+{
+    fn default() -> Self {
+        Self::new()
     }
+}
 
-    fn changed(self: &Self, action: Action){
-        if let Some(handler) = self.on_changed {
-            handler(Status {
-                action,
-                count: self.count,
-                usage: self.head,
-                maximum_usage: self.maximum_usage
-            })
+impl<const SIZE: usize, const ALIGNMENT: usize> Bump<SIZE, ALIGNMENT>
+where
+    Alignment<ALIGNMENT>: Aligned,
+{
+    /// `new` is `const`, so an allocator can be placed in a static.
+    ///
+    /// ```
+    /// use bump_malloc_free::{Bump, MallocFree};
+    ///
+    /// static mut HEAP: Bump<65536, 8> = Bump::new();
+    ///
+    /// let heap = unsafe { &*core::ptr::addr_of!(HEAP) };
+    /// let ptr = heap.malloc(16);
+    /// unsafe { heap.free(ptr) };
+    /// ```
+    ///
+    /// Misconfigured allocators are rejected at compile time: `ALIGNMENT` by the `Aligned`
+    /// bound, which only powers of two implement, and `SIZE` by an assertion.
+    ///
+    /// ```compile_fail
+    /// let bump = bump_malloc_free::Bump::<1024, 3>::new();
+    /// ```
+    ///
+    /// ```compile_fail
+    /// let bump = bump_malloc_free::Bump::<{ usize::MAX }, 8>::new();
+    /// ```
+    pub const fn new() -> Self {
+        const {
+            assert!(SIZE <= isize::MAX as usize, "SIZE must not exceed isize::MAX");
+        }
+        Self {
+            arena: Arena::new(),
+            heap: Heap::new(),
+            allocator: Cell::new(Allocator::from_context::<Self>(core::ptr::null_mut())),
+            _pinned: PhantomPinned,
         }
     }
 
-}
+    /// Moves `value` into the heap. Returns `None` if the heap is full.
+    ///
+    /// The block is counted like any other allocation and `value` is never dropped.
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc<T>(self: &Self, value: T) -> Option<&mut T> {
+        Some(self.alloc_uninit::<T>()?.write(value))
+    }
 
-impl<const SIZE: usize, const ALIGNMENT: usize> MallocFree for Bump<SIZE, ALIGNMENT> {
-    fn malloc(self: &mut Self, size: usize) -> *mut c_void {
-        let next_head = self.head + ((size + ALIGNMENT - 1) / ALIGNMENT) * ALIGNMENT;
-        if next_head > SIZE {
-            self.changed(Action::Error);
-            return core::ptr::null_mut();
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_uninit<T>(self: &Self) -> Option<&mut MaybeUninit<T>> {
+        let ptr = self.aligned_malloc(size_of::<T>(), align_of::<T>()) as *mut MaybeUninit<T>;
+        //a non-null block is aligned for T, sized for T and not handed out again while counted
+        unsafe { ptr.as_mut() }
+    }
+
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_slice_copy<T: Copy>(self: &Self, values: &[T]) -> Option<&mut [T]> {
+        let ptr = self.aligned_malloc(size_of_val(values), align_of::<T>()) as *mut T;
+        if ptr.is_null() {
+            return None;
         }
-        let result = &mut self.heap[self.head] as *mut u8;
-        self.head = next_head;
-        if self.maximum_usage < self.head {
-            self.maximum_usage = self.head;
+        unsafe {
+            core::ptr::copy_nonoverlapping(values.as_ptr(), ptr, values.len());
+            Some(core::slice::from_raw_parts_mut(ptr, values.len()))
         }
-        self.count = self.count + 1;
-        self.changed(Action::Malloc);
-        result as *mut c_void
     }
 
-    fn free(self: &mut Self, _ptr: *mut c_void) {
-        //if no items are used, reset the head
-        if self.count > 0 {
-            self.count = self.count - 1;
-            if self.count == 0 {
-                self.head = 0;
-            }
-        }
-        self.changed(Action::Free);
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_str(self: &Self, value: &str) -> Option<&mut str> {
+        let bytes = self.alloc_slice_copy(value.as_bytes())?;
+        Some(unsafe { core::str::from_utf8_unchecked_mut(bytes) })
     }
 
-    fn get_allocator(self: &mut Self) -> Allocator {
-        unsafe { core::mem::transmute(self as &mut dyn MallocFree) }
+    fn memory(self: &Self) -> Memory {
+        Memory {
+            base: self.heap.as_ptr(),
+            size: SIZE,
+            alignment: ALIGNMENT,
+        }
     }
 }
 
+arena_allocator!([const SIZE: usize, const ALIGNMENT: usize] Bump<SIZE, ALIGNMENT> where [Alignment<ALIGNMENT>: Aligned]);
+
 impl Allocator {
-    pub fn get_handle(self: Self) -> AllocatorHandle {
-        return &self;
+    pub fn new<M: MallocFree>(malloc_free: Pin<&M>) -> Self {
+        Self::from_context::<M>(malloc_free.get_ref() as *const M as *mut c_void)
     }
-}
 
-impl<const SIZE: usize, const ALIGNMENT: usize> Drop for Bump<SIZE, ALIGNMENT> {
-    fn drop(self: &mut Self) {
-        if self.count > 0 {
-            (self.on_drop_without_free)();
+    pub(crate) const fn from_context<M: MallocFree>(context: *mut c_void) -> Self {
+        Self {
+            context,
+            malloc: dispatch_malloc::<M>,
+            free: dispatch_free::<M>,
+            realloc: dispatch_realloc::<M>,
+            status: dispatch_status::<M>,
+            calloc: dispatch_calloc::<M>,
+            aligned_malloc: dispatch_aligned_malloc::<M>,
+            mark: dispatch_mark::<M>,
+            release: dispatch_release::<M>,
+            on_changed: dispatch_on_changed::<M>,
+            on_drop_without_free: dispatch_on_drop_without_free::<M>,
         }
     }
 }
 
-#[cfg(test)]
+unsafe extern "C" fn dispatch_malloc<M: MallocFree>(context: *mut c_void, size: usize) -> *mut c_void {
+    (*(context as *const M)).malloc(size)
+}
+
+unsafe extern "C" fn dispatch_free<M: MallocFree>(context: *mut c_void, ptr: *mut c_void) {
+    (*(context as *const M)).free(ptr)
+}
+
+unsafe extern "C" fn dispatch_realloc<M: MallocFree>(context: *mut c_void, ptr: *mut c_void, size: usize) -> *mut c_void {
+    (*(context as *const M)).realloc(ptr, size)
+}
+
+unsafe extern "C" fn dispatch_status<M: MallocFree>(context: *mut c_void) -> Status {
+    (*(context as *const M)).get_status()
+}
+
+unsafe extern "C" fn dispatch_calloc<M: MallocFree>(context: *mut c_void, nmemb: usize, size: usize) -> *mut c_void {
+    (*(context as *const M)).calloc(nmemb, size)
+}
+
+unsafe extern "C" fn dispatch_aligned_malloc<M: MallocFree>(context: *mut c_void, size: usize, alignment: usize) -> *mut c_void {
+    (*(context as *const M)).aligned_malloc(size, alignment)
+}
+
+unsafe extern "C" fn dispatch_mark<M: MallocFree>(context: *mut c_void) -> Marker {
+    (*(context as *const M)).mark()
+}
+
+unsafe extern "C" fn dispatch_release<M: MallocFree>(context: *mut c_void, marker: Marker) -> c_int {
+    if (*(context as *const M)).release(marker) {
+        0
+    } else {
+        EINVAL
+    }
+}
+
+unsafe extern "C" fn dispatch_on_changed<M: MallocFree>(context: *mut c_void, observer: Option<OnChangedC>, observer_context: *mut c_void) {
+    (*(context as *const M)).handle_on_changed_c(observer, observer_context)
+}
+
+unsafe extern "C" fn dispatch_on_drop_without_free<M: MallocFree>(context: *mut c_void, handler: Option<OnDropWithoutFreeC>, handler_context: *mut c_void) {
+    (*(context as *const M)).handle_drop_without_free_c(handler, handler_context)
+}
+
+/// # Safety
+///
+/// `handle` must point to a live `Allocator`.
+#[no_mangle]
+pub unsafe extern "C" fn bump_malloc(handle: AllocatorHandle, size: usize) -> *mut c_void {
+    ((*handle).malloc)((*handle).context, size)
+}
+
+/// # Safety
+///
+/// `handle` must point to a live `Allocator` and `ptr` must have been allocated through it.
+#[no_mangle]
+pub unsafe extern "C" fn bump_free(handle: AllocatorHandle, ptr: *mut c_void) {
+    ((*handle).free)((*handle).context, ptr)
+}
+
+/// # Safety
+///
+/// `handle` must point to a live `Allocator`.
+#[no_mangle]
+pub unsafe extern "C" fn bump_calloc(handle: AllocatorHandle, nmemb: usize, size: usize) -> *mut c_void {
+    ((*handle).calloc)((*handle).context, nmemb, size)
+}
+
+/// # Safety
+///
+/// `handle` must point to a live `Allocator` and `ptr` must be null or have been allocated through it.
+#[no_mangle]
+pub unsafe extern "C" fn bump_realloc(handle: AllocatorHandle, ptr: *mut c_void, size: usize) -> *mut c_void {
+    ((*handle).realloc)((*handle).context, ptr, size)
+}
+
+/// # Safety
+///
+/// `handle` must point to a live `Allocator`.
+#[no_mangle]
+pub unsafe extern "C" fn bump_aligned_alloc(handle: AllocatorHandle, alignment: usize, size: usize) -> *mut c_void {
+    ((*handle).aligned_malloc)((*handle).context, size, alignment)
+}
+
+const EINVAL: c_int = 22;
+const ENOMEM: c_int = 12;
+
+/// # Safety
+///
+/// `handle` must point to a live `Allocator` and `memptr` must be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn bump_posix_memalign(handle: AllocatorHandle, memptr: *mut *mut c_void, alignment: usize, size: usize) -> c_int {
+    if !alignment.is_power_of_two() || !alignment.is_multiple_of(core::mem::size_of::<*mut c_void>()) {
+        return EINVAL;
+    }
+    let result = bump_aligned_alloc(handle, alignment, size);
+    if result.is_null() {
+        return ENOMEM;
+    }
+    *memptr = result;
+    0
+}
+
+/// # Safety
+///
+/// `handle` must point to a live `Allocator`.
+#[no_mangle]
+pub unsafe extern "C" fn bump_status(handle: AllocatorHandle) -> Status {
+    ((*handle).status)((*handle).context)
+}
+
+/// # Safety
+///
+/// `handle` must point to a live `Allocator`.
+#[no_mangle]
+pub unsafe extern "C" fn bump_mark(handle: AllocatorHandle) -> Marker {
+    ((*handle).mark)((*handle).context)
+}
+
+/// Returns 0, or `EINVAL` if a marker taken after `marker` has not been released yet.
+///
+/// # Safety
+///
+/// `handle` must point to a live `Allocator` and blocks allocated since `marker` must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn bump_release(handle: AllocatorHandle, marker: Marker) -> c_int {
+    ((*handle).release)((*handle).context, marker)
+}
+
+/// Calls `observer` with `context` on every change, null removes the observer.
+///
+/// # Safety
+///
+/// `handle` must point to a live `Allocator` and `context` must stay valid while `observer` is registered.
+#[no_mangle]
+pub unsafe extern "C" fn bump_handle_on_changed(handle: AllocatorHandle, observer: Option<OnChangedC>, context: *mut c_void) {
+    ((*handle).on_changed)((*handle).context, observer, context)
+}
+
+/// Calls `handler` with `context` and the number of allocations still in use when the allocator
+/// is dropped, destroyed or force reset with allocations outstanding. Null removes the handler.
+///
+/// # Safety
+///
+/// `handle` must point to a live `Allocator` and `context` must stay valid while `handler` is registered.
+#[no_mangle]
+pub unsafe extern "C" fn bump_handle_drop_without_free(handle: AllocatorHandle, handler: Option<OnDropWithoutFreeC>, context: *mut c_void) {
+    ((*handle).on_drop_without_free)((*handle).context, handler, context)
+}
+
+#[cfg(all(test, feature = "std"))]
 mod tests {
     use super::*;
 
-    #[cfg(feature = "std")]
+    pub(crate) type Event = (Action, usize, usize, usize, Reason);
+
+    //what an allocator reported, recorded through its own closure so tests share no state
+    #[derive(Clone, Default)]
+    pub(crate) struct Events(std::sync::Arc<std::sync::Mutex<Vec<Event>>>);
+
+    impl Events {
+        pub(crate) fn handler(self: &Self) -> Box<dyn FnMut(Status) + Send> {
+            let events = self.clone();
+            Box::new(move |status: Status| {
+                let event = (status.action, status.ptr as usize, status.requested_size, status.rounded_size, status.reason);
+                events.0.lock().unwrap().push(event);
+            })
+        }
+
+        pub(crate) fn all(self: &Self) -> Vec<Event> {
+            self.0.lock().unwrap().clone()
+        }
+
+        pub(crate) fn count(self: &Self, action: Action) -> usize {
+            self.all().iter().filter(|event| event.0 == action).count()
+        }
+    }
+
     #[test]
     fn bump_malloc_free() {
         type BigBump = Bump<1024, 8>;
         fn get_location(bump: &BigBump, location: usize) -> *const c_void {
-            &bump.heap[location] as *const u8 as *const c_void
+            bump.heap.as_ptr().wrapping_add(location) as *const c_void
         }
 
-        let mut bump = BigBump::new();
+        let bump = BigBump::new();
         let bottom_of_heap = get_location(&bump, 0);
 
         let first = bump.malloc(20);
@@ -155,9 +608,569 @@ mod tests {
 
         let no_space = bump.malloc(1024);
         assert_eq!(no_space, core::ptr::null_mut());
-        bump.free(first);
-        assert_ne!(bottom_of_heap, get_location(&bump, bump.head));
-        bump.free(first);
-        assert_eq!(bottom_of_heap, get_location(&bump, bump.head));
+        unsafe { bump.free(first) };
+        assert_ne!(bottom_of_heap, get_location(&bump, bump.arena.head.get()));
+        //the second free is rejected, so the heap is not rewound under `second`
+        unsafe { bump.free(first) };
+        assert_eq!(bump.get_count(), 1);
+        assert_ne!(bottom_of_heap, get_location(&bump, bump.arena.head.get()));
+    }
+
+    #[test]
+    fn bump_extern_c() {
+        let bump = core::pin::pin!(Bump::<256, 8>::new());
+        let handle = bump.as_ref().get_handle();
+        unsafe {
+            let first = bump_malloc(handle, 4) as *mut u8;
+            first.copy_from([1, 2, 3, 4].as_ptr(), 4);
+            let grown = bump_realloc(handle, first as *mut c_void, 12) as *mut u8;
+            assert_eq!(core::slice::from_raw_parts(grown, 4), &[1, 2, 3, 4]);
+            let zeroed = bump_calloc(handle, 4, 4) as *mut u8;
+            assert_eq!(core::slice::from_raw_parts(zeroed, 16), &[0; 16]);
+            assert_eq!(bump_calloc(handle, usize::MAX, 2), core::ptr::null_mut());
+            bump_free(handle, grown as *mut c_void);
+            bump_free(handle, zeroed as *mut c_void);
+        }
+        assert_eq!(bump.get_count(), 0);
+    }
+
+    #[test]
+    fn bump_handle_is_stable() {
+        let bump = core::pin::pin!(Bump::<64, 8>::new());
+        let handle = bump.as_ref().get_handle();
+        let first = unsafe { bump_malloc(handle, 8) } as *mut u64;
+        let second = bump.malloc(8) as *mut u64;
+        unsafe {
+            first.write(1);
+            second.write(2);
+            assert_eq!(first.read(), 1);
+            bump_free(handle, first as *mut c_void);
+            bump_free(handle, second as *mut c_void);
+        }
+        assert_eq!(bump.get_count(), 0);
+    }
+
+    #[test]
+    fn bump_vtable() {
+        let bump = core::pin::pin!(Bump::<64, 8>::new());
+        let allocator = unsafe { *bump.as_ref().get_handle() };
+        let first = unsafe { (allocator.malloc)(allocator.context, 10) };
+        assert!(!first.is_null());
+        let status = unsafe { (allocator.status)(allocator.context) };
+        assert_eq!(status.action, Action::Query);
+        assert_eq!(status.count, 1);
+        assert_eq!(status.usage, 16 + arena::BLOCK_SIZE);
+        unsafe { (allocator.free)(allocator.context, first) };
+        assert_eq!(bump.get_status().count, 0);
+    }
+
+    //implements only what the defaults cannot provide
+    struct Minimal(Bump<256, 8>);
+
+    unsafe impl MallocFree for Minimal {
+        fn malloc(self: &Self, size: usize) -> *mut c_void {
+            self.0.malloc(size)
+        }
+
+        unsafe fn free(self: &Self, ptr: *mut c_void) {
+            self.0.free(ptr)
+        }
+
+        unsafe fn realloc(self: &Self, ptr: *mut c_void, size: usize) -> *mut c_void {
+            self.0.realloc(ptr, size)
+        }
+
+        fn get_status(self: &Self) -> Status {
+            self.0.get_status()
+        }
+
+        fn aligned_malloc(self: &Self, size: usize, alignment: usize) -> *mut c_void {
+            self.0.aligned_malloc(size, alignment)
+        }
+    }
+
+    #[test]
+    fn malloc_free_defaults() {
+        let minimal = core::pin::pin!(Minimal(Bump::new()));
+        let allocator = Allocator::new(minimal.as_ref());
+        let handle: AllocatorHandle = &allocator;
+        unsafe {
+            let zeroed = bump_calloc(handle, 4, 4) as *mut u8;
+            assert_eq!(core::slice::from_raw_parts(zeroed, 16), [0; 16]);
+            assert!(bump_calloc(handle, usize::MAX, 2).is_null());
+            zeroed.write_bytes(7, 16);
+            let moved = minimal.aligned_realloc(zeroed as *mut c_void, 24, 32) as *mut u8;
+            assert_eq!(moved as usize % 32, 0);
+            assert_eq!(core::slice::from_raw_parts(moved, 16), [7; 16]);
+            assert_eq!(minimal.get_status().count, 1);
+            assert_eq!(bump_release(handle, bump_mark(handle)), EINVAL);
+            bump_handle_on_changed(handle, None, core::ptr::null_mut());
+            bump_free(handle, moved as *mut c_void);
+        }
+        assert_eq!(minimal.get_status().count, 0);
+    }
+
+    #[test]
+    fn bump_rejects_unissued_markers() {
+        let mut bump = Bump::<256, 8>::new();
+        bump.malloc(8);
+        assert!(!bump.reset_to(Minimal(Bump::new()).mark()));
+        assert_eq!(bump.get_count(), 1);
+        let bump = core::pin::pin!(bump);
+        let handle = bump.as_ref().get_handle();
+        //a zero-initialised bump_marker_t
+        assert_eq!(unsafe { bump_release(handle, core::mem::zeroed()) }, EINVAL);
+        assert_eq!(bump.get_count(), 1);
+    }
+
+    #[test]
+    fn bump_block_overhead() {
+        let bump = Bump::<1024, 8>::new();
+        let blocks = core::iter::repeat_with(|| bump.malloc(8)).take_while(|ptr| !ptr.is_null()).count();
+        assert_eq!(blocks, 1024 / (8 + arena::BLOCK_SIZE));
+        assert_eq!(bump.get_status().usage, blocks * (8 + arena::BLOCK_SIZE));
+        assert!(Bump::<1024, 8>::new().malloc(1024).is_null());
+    }
+
+    #[test]
+    fn bump_moves_to_thread() {
+        let mut bump = Bump::<256, 8>::new();
+        bump.handle_on_changed(|_status| {});
+        let count = std::thread::spawn(move || {
+            let bump = core::pin::pin!(bump);
+            unsafe { bump_malloc(bump.as_ref().get_handle(), 8) };
+            bump.get_count()
+        });
+        assert_eq!(count.join().unwrap(), 1);
+
+        fn assert_send<T: Send>() {}
+        assert_send::<BumpRegion<'static>>();
+    }
+
+    #[test]
+    fn bump_realloc_copies_only_the_block() {
+        let bump = Bump::<256, 8>::new();
+        let ptr = bump.malloc(8) as *mut u8;
+        let neighbour = bump.malloc(8) as *mut u8;
+        unsafe {
+            ptr.write_bytes(1, 8);
+            neighbour.write_bytes(2, 8);
+            let moved = bump.realloc(ptr as *mut c_void, 32) as *mut u8;
+            assert_ne!(moved, ptr);
+            assert_eq!(core::slice::from_raw_parts(moved, 8), [1; 8]);
+            //the neighbouring block is not read into the new one
+            assert_eq!(core::slice::from_raw_parts(moved.add(8), 24), [0; 24]);
+        }
+    }
+
+    #[test]
+    fn bump_realloc_in_place() {
+        let bump = Bump::<256, 8>::new();
+        let first = bump.malloc(8);
+        let second = bump.malloc(8);
+        unsafe {
+            (first as *mut u64).write(1);
+            (second as *mut u64).write(2);
+
+            //the most recent block grows and shrinks without moving
+            assert_eq!(bump.realloc(second, 64), second);
+            assert_eq!(bump.arena.head.get(), 72);
+            assert_eq!(bump.realloc(second, 16), second);
+            assert_eq!(bump.arena.head.get(), 24);
+
+            //shrinking an older block keeps it where it is
+            assert_eq!(bump.realloc(first, 4), first);
+
+            //growing an older block moves it and copies the contents
+            let moved = bump.realloc(first, 32);
+            assert_eq!(moved as usize, second as usize + 16);
+            assert_eq!((moved as *mut u32).read(), 1);
+            assert_eq!((second as *mut u64).read(), 2);
+            assert_eq!(bump.get_count(), 2);
+
+            //growing past the end of the heap leaves the block alone
+            assert_eq!(bump.realloc(moved, 1024), core::ptr::null_mut());
+            assert_eq!(bump.get_count(), 2);
+
+            bump.free(moved);
+            bump.free(second);
+        }
+        assert_eq!(bump.arena.head.get(), 0);
+    }
+
+    #[test]
+    fn bump_calloc_overflow() {
+        let events = Events::default();
+        let mut bump = Bump::<256, 8>::new();
+        bump.handle_on_changed_boxed(events.handler());
+        let dirty = bump.malloc(32);
+        unsafe { core::ptr::write_bytes(dirty as *mut u8, 0xff, 32) };
+        //shrinking the top block hands the dirty bytes back to the heap
+        unsafe { bump.realloc(dirty, 8) };
+
+        assert_eq!(bump.calloc(usize::MAX / 2, 3), core::ptr::null_mut());
+        assert_eq!(events.count(Action::Overflow), 1);
+        assert_eq!(bump.get_count(), 1);
+
+        let zeroed = bump.calloc(3, 4);
+        assert_eq!(zeroed as usize, dirty as usize + 8);
+        assert_eq!(unsafe { core::slice::from_raw_parts(zeroed as *const u8, 12) }, &[0; 12]);
+        unsafe {
+            bump.free(zeroed);
+            bump.free(dirty);
+        }
+    }
+
+    #[test]
+    fn bump_aligned_malloc() {
+        let bump = core::pin::pin!(Bump::<1024, 8>::new());
+        let first = bump.malloc(4);
+        let aligned = bump.aligned_malloc(16, 128);
+        assert_eq!(aligned as usize % 128, 0);
+        //later requests go back to the default alignment
+        let after = bump.malloc(4);
+        assert_eq!(after as usize, aligned as usize + 16);
+        assert_eq!(bump.aligned_malloc(16, 24), core::ptr::null_mut());
+
+        let handle = bump.as_ref().get_handle();
+        unsafe {
+            let from_c = bump_aligned_alloc(handle, 64, 8);
+            assert_eq!(from_c as usize % 64, 0);
+            let mut memptr = core::ptr::null_mut();
+            assert_eq!(bump_posix_memalign(handle, &mut memptr, 256, 8), 0);
+            assert_eq!(memptr as usize % 256, 0);
+            assert_eq!(bump_posix_memalign(handle, &mut memptr, 2, 8), EINVAL);
+            //larger than the whole heap, wherever it happens to be placed
+            assert_eq!(bump_posix_memalign(handle, &mut memptr, 8, 2048), ENOMEM);
+            for ptr in [first, aligned, after, from_c, memptr] {
+                bump.free(ptr);
+            }
+        }
+        assert_eq!(bump.get_count(), 0);
+    }
+
+    #[test]
+    fn bump_heap_is_aligned() {
+        #[repr(C)]
+        struct Placed<const OFFSET: usize> {
+            padding: [u8; OFFSET],
+            bump: Bump<512, 64>,
+        }
+
+        fn check<const OFFSET: usize>() {
+            let placed = Placed::<OFFSET> {
+                padding: [0; OFFSET],
+                bump: Bump::new(),
+            };
+            let bump = &placed.bump;
+            assert_eq!(bump.heap.as_ptr() as usize % 64, 0);
+            let first = bump.malloc(1);
+            assert_eq!(first as *mut u8, bump.heap.as_ptr());
+            let mut allocations = vec![first];
+            for size in [3, 17, 64, 5] {
+                allocations.push(bump.malloc(size));
+            }
+            for ptr in allocations {
+                assert_eq!(ptr as usize % 64, 0);
+                unsafe { bump.free(ptr) };
+            }
+        }
+
+        check::<1>();
+        check::<3>();
+        check::<7>();
+        check::<9>();
+    }
+
+    #[test]
+    fn bump_size_overflow() {
+        let events = Events::default();
+        let mut bump = Bump::<256, 8>::new();
+        bump.handle_on_changed_boxed(events.handler());
+        let first = bump.malloc(8);
+        for size in [usize::MAX, usize::MAX - 7] {
+            assert_eq!(bump.malloc(size), core::ptr::null_mut());
+        }
+        assert_eq!(bump.aligned_malloc(usize::MAX - 7, 16), core::ptr::null_mut());
+        //too big but representable is an ordinary allocation failure
+        assert_eq!(bump.malloc(1 << (usize::BITS - 1)), core::ptr::null_mut());
+        assert_eq!(unsafe { bump.realloc(first, usize::MAX) }, core::ptr::null_mut());
+        assert_eq!((events.count(Action::Overflow), events.count(Action::Error)), (4, 1));
+        assert_eq!(bump.get_count(), 1);
+        assert_eq!(bump.arena.head.get(), 8);
+        unsafe { bump.free(first) };
+    }
+
+    #[test]
+    fn bump_in_static() {
+        static mut HEAP: Bump<4096, 16> = Bump::new();
+        let heap = Pin::static_ref(unsafe { &*core::ptr::addr_of!(HEAP) });
+        let handle = heap.get_handle();
+        let ptr = unsafe { bump_malloc(handle, 100) };
+        assert_eq!(ptr as *mut u8, heap.heap.as_ptr());
+        unsafe { bump_free(handle, ptr) };
+        assert_eq!(heap.get_count(), 0);
+    }
+
+    #[test]
+    fn bump_typed_alloc() {
+        let bump = Bump::<1024, 4>::new();
+        let byte = bump.alloc(7u8).unwrap();
+        let wide = bump.alloc(u128::MAX).unwrap();
+        assert_eq!(wide as *mut u128 as usize % align_of::<u128>(), 0);
+        *byte += 1;
+        assert_eq!((*byte, *wide), (8, u128::MAX));
+
+        let words = bump.alloc_slice_copy(&[1u64, 2, 3]).unwrap();
+        words[0] = 4;
+        assert_eq!(words, &[4, 2, 3]);
+        let name = bump.alloc_str("bump").unwrap();
+        name.make_ascii_uppercase();
+        assert_eq!(name, "BUMP");
+        let uninit = bump.alloc_uninit::<[u32; 4]>().unwrap();
+        assert_eq!(uninit.write([5; 4]), &[5; 4]);
+        assert_eq!(bump.get_count(), 5);
+
+        assert!(bump.alloc([0u8; 1024]).is_none());
+        assert_eq!(bump.get_count(), 5);
+    }
+
+    #[test]
+    fn bump_markers() {
+        let mut bump = Bump::<1024, 8>::new();
+        let kept = bump.malloc(8);
+        let outer = bump.mark();
+        let freed_inside = bump.malloc(16);
+        let inner = bump.mark();
+        bump.malloc(32);
+        //only the innermost marker can be released
+        assert!(!bump.reset_to(outer));
+        assert_eq!(bump.get_count(), 3);
+        assert!(bump.reset_to(inner));
+        assert_eq!((bump.get_count(), bump.arena.head.get()), (2, 24));
+        assert!(!bump.reset_to(inner));
+
+        unsafe {
+            bump.free(freed_inside);
+            //memory below a marker stays put even when nothing is in use
+            bump.free(kept);
+        }
+        assert_eq!((bump.get_count(), bump.arena.head.get()), (0, 8));
+        bump.malloc(8);
+        assert!(bump.reset_to(outer));
+        assert_eq!((bump.get_count(), bump.arena.head.get()), (0, 0));
+    }
+
+    #[test]
+    fn bump_markers_extern_c() {
+        let bump = core::pin::pin!(Bump::<256, 8>::new());
+        let handle = bump.as_ref().get_handle();
+        unsafe {
+            let kept = bump_malloc(handle, 8);
+            let outer = bump_mark(handle);
+            let grown = bump_realloc(handle, kept, 16);
+            //the block below the marker cannot grow in place
+            assert_ne!(grown, kept);
+            let inner = bump_mark(handle);
+            assert_eq!(bump_release(handle, outer), EINVAL);
+            assert_eq!(bump_release(handle, inner), 0);
+            assert_eq!(bump_release(handle, outer), 0);
+            assert_eq!(bump_status(handle).count, 0);
+            assert_eq!(bump.arena.head.get(), 0);
+        }
+    }
+
+    #[test]
+    fn bump_reset() {
+        unsafe extern "C" fn on_drop_without_free(context: *mut c_void, count: usize) {
+            *(context as *mut usize) += count;
+        }
+
+        let mut leaked = 0usize;
+        let events = Events::default();
+        let mut bump = Bump::<256, 8>::new();
+        unsafe { bump.handle_drop_without_free_c(Some(on_drop_without_free), core::ptr::addr_of_mut!(leaked) as *mut c_void) };
+        bump.handle_on_changed_boxed(events.handler());
+        let ptr = bump.malloc(8);
+        assert!(!bump.reset());
+        assert_eq!(bump.get_count(), 1);
+        unsafe { bump.free(ptr) };
+        assert!(bump.reset());
+
+        bump.mark();
+        bump.malloc(8);
+        bump.malloc(8);
+        assert_eq!(bump.force_reset(), 2);
+        assert_eq!((bump.get_count(), bump.arena.head.get(), bump.arena.depth.get()), (0, 0, 0));
+        assert_eq!((leaked, events.count(Action::Reset)), (2, 2));
+
+        bump.malloc(8);
+        drop(bump);
+        assert_eq!(leaked, 3);
+    }
+
+    #[test]
+    fn bump_lifo() {
+        let mut bump = Bump::<256, 8>::new();
+        bump.set_lifo(true);
+        let bottom = bump.malloc(8);
+        let middle = bump.malloc(8);
+        let top = bump.malloc(8);
+        unsafe {
+            //freeing below the top only marks the block
+            bump.free(middle);
+            assert_eq!(bump.arena.head.get(), 24);
+            //popping the top cascades through the freed block beneath it
+            bump.free(top);
+            assert_eq!((bump.arena.head.get(), bump.arena.blocks.get()), (8, 1));
+            assert_eq!(bump.malloc(16), middle);
+            assert_eq!(bump.get_count(), 2);
+        }
+
+        //blocks below a marker are not popped
+        let marker = bump.mark();
+        let above = bump.malloc(8);
+        unsafe {
+            bump.free(above);
+            assert_eq!(bump.arena.head.get(), 24);
+            bump.free(middle);
+            assert_eq!(bump.arena.head.get(), 24);
+            //freed blocks uncovered by a release are popped too
+            assert!(bump.release(marker));
+            assert_eq!(bump.arena.head.get(), 8);
+            bump.free(bottom);
+        }
+        assert_eq!(bump.arena.head.get(), 0);
+
+        //the padding in front of an over-aligned block goes with it
+        let mut bump = Bump::<1024, 8>::new();
+        bump.set_lifo(true);
+        let first = bump.malloc(8);
+        let usage = bump.get_status().usage;
+        let aligned = bump.aligned_malloc(8, 256);
+        unsafe { bump.free(aligned) };
+        assert_eq!(bump.get_status().usage, usage);
+        unsafe { bump.free(first) };
+    }
+
+    #[test]
+    fn bump_invalid_free() {
+        let events = Events::default();
+        let mut bump = Bump::<256, 8>::new();
+        bump.handle_on_changed_boxed(events.handler());
+        let other = Bump::<256, 8>::new();
+        let first = bump.malloc(16);
+        let second = bump.malloc(8);
+        unsafe { bump.free(second) };
+        let mut local = 0u64;
+        let invalid = [
+            &mut local as *mut u64 as *mut c_void,
+            (first as *mut u8).wrapping_add(8) as *mut c_void,
+            other.malloc(8),
+            bump.heap.as_ptr().wrapping_add(256) as *mut c_void,
+        ];
+        let status = bump.get_status();
+        for ptr in invalid {
+            unsafe { bump.free(ptr) };
+        }
+        assert_eq!(events.count(Action::InvalidFree), 4);
+        assert_eq!((bump.get_count(), bump.get_status().usage), (status.count, status.usage));
+
+        //null is ignored like in C
+        unsafe { bump.free(core::ptr::null_mut()) };
+        unsafe { bump.free(first) };
+        assert_eq!(bump.get_count(), 0);
+        //blocks are forgotten once the heap rewinds, the debug feature reports a double free instead
+        unsafe { bump.free(first) };
+        assert_eq!(events.count(Action::InvalidFree), if cfg!(feature = "debug") { 4 } else { 5 });
+        assert_eq!(bump.get_count(), 0);
+    }
+
+    #[cfg(feature = "debug")]
+    #[test]
+    fn bump_double_free() {
+        let events = Events::default();
+        let double_freed = || events.all().iter().filter(|event| event.0 == Action::DoubleFree).map(|event| event.1).collect::<Vec<_>>();
+        let mut bump = Bump::<256, 8>::new();
+        bump.handle_on_changed_boxed(events.handler());
+        let first = bump.malloc(8);
+        let second = bump.malloc(8);
+        unsafe {
+            bump.free(first);
+            bump.free(first);
+        }
+        assert_eq!(double_freed(), [first as usize]);
+        assert_eq!(bump.get_count(), 1);
+        unsafe { bump.free(second) };
+        assert_eq!(bump.get_count(), 0);
+
+        //the heap rewound after the only block was freed
+        let only = bump.malloc(8);
+        unsafe {
+            bump.free(only);
+            bump.free(only);
+        }
+        assert_eq!(double_freed(), [first as usize, only as usize]);
+
+        //popped off the top in LIFO mode
+        bump.set_lifo(true);
+        let kept = bump.malloc(8);
+        let popped = bump.malloc(8);
+        unsafe {
+            bump.free(popped);
+            bump.free(popped);
+        }
+        assert_eq!(double_freed(), [first as usize, only as usize, popped as usize]);
+        assert_eq!(bump.get_count(), 1);
+
+        //once the heap grows over the entry, the block is no longer known
+        let lower = bump.malloc(8);
+        let upper = bump.malloc(8);
+        unsafe {
+            bump.free(upper);
+            bump.free(lower);
+        }
+        let large = bump.malloc(208);
+        assert!(!large.is_null());
+        unsafe {
+            bump.free(upper);
+            bump.free(large);
+            bump.free(kept);
+        }
+        assert_eq!(bump.get_count(), 0);
+        assert_eq!(events.count(Action::DoubleFree), 3);
+    }
+
+    #[test]
+    fn bump_status_details() {
+        let events = Events::default();
+        let mut bump = Bump::<256, 8>::new();
+        bump.handle_on_changed_boxed(events.handler());
+        let ptr = bump.malloc(5);
+        let grown = unsafe { bump.realloc(ptr, 12) };
+        bump.malloc(512);
+        bump.calloc(usize::MAX, 2);
+        unsafe {
+            bump.free(grown);
+            //the heap rewound, only the debug feature still recognizes the block
+            bump.free(grown);
+        }
+        let ptr = ptr as usize;
+        let second_free = if cfg!(feature = "debug") {
+            (Action::DoubleFree, ptr, 0, 16, Reason::DoubleFree)
+        } else {
+            (Action::InvalidFree, ptr, 0, 0, Reason::InvalidFree)
+        };
+        assert_eq!(
+            events.all(),
+            [
+                (Action::Malloc, ptr, 5, 8, Reason::None),
+                (Action::Realloc, ptr, 12, 16, Reason::None),
+                (Action::Error, 0, 512, 512, Reason::OutOfMemory),
+                (Action::Overflow, 0, usize::MAX, 0, Reason::Overflow),
+                (Action::Free, ptr, 0, 16, Reason::None),
+                second_free,
+            ]
+        );
     }
 }
@@ -0,0 +1,165 @@
+use core::ffi::c_void;
+
+use crate::{OnDropWithoutFree, Status};
+
+/// Receives every `Status` change of an allocator and may keep state of its own.
+pub trait AllocObserver {
+    fn on_changed(self: &mut Self, status: Status);
+}
+
+/// Observer for C consumers, called with the context it was registered with.
+pub type OnChangedC = unsafe extern "C" fn(*mut c_void, *const Status);
+
+#[derive(Default)]
+pub(crate) enum Observer {
+    #[default]
+    None,
+    Function(fn(Status)),
+    //registered through an unsafe call promising it outlives the registration
+    Object(*mut (dyn AllocObserver + Send)),
+    #[cfg(feature = "std")]
+    Boxed(Box<dyn FnMut(Status) + Send>),
+    C(OnChangedC, *mut c_void),
+}
+
+//objects and closures are `Send`, and whoever registers a C observer promises its context may
+//follow the allocator to another thread
+unsafe impl Send for Observer {}
+
+/// Drop-without-free handler for C consumers, called with the context it was registered with
+/// and the number of allocations that were never freed.
+pub type OnDropWithoutFreeC = unsafe extern "C" fn(*mut c_void, usize);
+
+#[derive(Copy, Clone, Default)]
+pub(crate) enum DropWithoutFree {
+    #[default]
+    None,
+    Function(OnDropWithoutFree),
+    C(OnDropWithoutFreeC, *mut c_void),
+}
+
+//whoever registers a C handler promises its context may follow the allocator to another thread
+unsafe impl Send for DropWithoutFree {}
+
+impl DropWithoutFree {
+    pub(crate) fn from_c(handler: Option<OnDropWithoutFreeC>, context: *mut c_void) -> Self {
+        match handler {
+            Some(handler) => DropWithoutFree::C(handler, context),
+            None => DropWithoutFree::None,
+        }
+    }
+
+    pub(crate) fn notify(self: Self, count: usize) {
+        match self {
+            DropWithoutFree::None => {}
+            DropWithoutFree::Function(handler) => handler(count),
+            DropWithoutFree::C(handler, context) => unsafe { handler(context, count) },
+        }
+    }
+}
+
+impl Observer {
+    pub(crate) fn from_c(observer: Option<OnChangedC>, context: *mut c_void) -> Self {
+        match observer {
+            Some(observer) => Observer::C(observer, context),
+            None => Observer::None,
+        }
+    }
+
+    pub(crate) fn is_none(self: &Self) -> bool {
+        matches!(self, Observer::None)
+    }
+
+    pub(crate) fn notify(self: &mut Self, status: Status) {
+        match self {
+            Observer::None => {}
+            Observer::Function(handler) => handler(status),
+            Observer::Object(observer) => unsafe { (**observer).on_changed(status) },
+            #[cfg(feature = "std")]
+            Observer::Boxed(handler) => handler(status),
+            Observer::C(handler, context) => unsafe { handler(*context, &status) },
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::{Action, Bump, MallocFree};
+
+    struct Counter {
+        mallocs: usize,
+    }
+
+    impl AllocObserver for Counter {
+        fn on_changed(self: &mut Self, status: Status) {
+            if status.action == Action::Malloc {
+                self.mallocs += 1;
+            }
+        }
+    }
+
+    #[test]
+    fn observers_keep_state() {
+        //a borrowed observer on the stack, no static needed
+        let mut counter = Counter { mallocs: 0 };
+        let mut bump = Bump::<256, 8>::new();
+        unsafe { bump.handle_on_changed_observer(core::ptr::addr_of_mut!(counter)) };
+        bump.malloc(8);
+        bump.malloc(8);
+
+        let events = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+        let recorded = events.clone();
+        bump.handle_on_changed_boxed(Box::new(move |status: Status| recorded.lock().unwrap().push(status.action)));
+        assert_eq!(counter.mallocs, 2);
+        bump.malloc(8);
+        bump.force_reset();
+        assert_eq!(*events.lock().unwrap(), [Action::Malloc, Action::Reset]);
+        assert_eq!(counter.mallocs, 2);
+    }
+
+    #[test]
+    fn c_observer_may_allocate() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        static MALLOCS: AtomicUsize = AtomicUsize::new(0);
+        unsafe extern "C" fn allocate_again(context: *mut c_void, status: *const Status) {
+            if (*status).action == Action::Malloc {
+                MALLOCS.fetch_add(1, Ordering::Relaxed);
+                (*(context as *const Bump<256, 8>)).malloc(8);
+            }
+        }
+
+        let bump = core::pin::pin!(Bump::<256, 8>::new());
+        let handle = bump.as_ref().get_handle();
+        unsafe {
+            crate::bump_handle_on_changed(handle, Some(allocate_again), (*handle).context);
+            //the allocation made by the observer is not reported back to it
+            crate::bump_malloc(handle, 8);
+            assert_eq!((MALLOCS.load(Ordering::Relaxed), bump.get_count()), (1, 2));
+            crate::bump_malloc(handle, 8);
+            assert_eq!((MALLOCS.load(Ordering::Relaxed), bump.get_count()), (2, 4));
+            crate::bump_handle_on_changed(handle, None, core::ptr::null_mut());
+            crate::bump_malloc(handle, 8);
+        }
+        assert_eq!((MALLOCS.load(Ordering::Relaxed), bump.get_count()), (2, 5));
+    }
+
+    #[test]
+    fn c_observer_may_unregister_itself() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+        unsafe extern "C" fn once(context: *mut c_void, _status: *const Status) {
+            CALLS.fetch_add(1, Ordering::Relaxed);
+            crate::bump_handle_on_changed(context as crate::AllocatorHandle, None, core::ptr::null_mut());
+        }
+
+        let bump = core::pin::pin!(Bump::<256, 8>::new());
+        let handle = bump.as_ref().get_handle();
+        unsafe {
+            crate::bump_handle_on_changed(handle, Some(once), handle as *mut c_void);
+            crate::bump_malloc(handle, 8);
+            crate::bump_malloc(handle, 8);
+        }
+        assert_eq!(CALLS.load(Ordering::Relaxed), 1);
+    }
+}
@@ -0,0 +1,187 @@
+use core::cell::Cell;
+use core::ffi::c_void;
+use core::marker::{PhantomData, PhantomPinned};
+
+use crate::arena::{arena_allocator, Arena, Memory};
+use crate::{Allocator, AllocatorHandle};
+
+/// Bump allocator over a caller-provided buffer with a runtime size and alignment.
+///
+/// The buffer start is rounded up to `alignment`, so the first allocation is aligned.
+pub struct BumpRegion<'a> {
+    arena: Arena,
+    memory: Memory,
+    allocator: Cell<Allocator>,
+    _buffer: PhantomData<&'a mut [u8]>,
+    _pinned: PhantomPinned,
+}
+
+impl<'a> BumpRegion<'a> {
+    /// Returns `None` if `alignment` is not a power of two.
+    pub fn new(buffer: &'a mut [u8], alignment: usize) -> Option<Self> {
+        unsafe { Self::from_raw_parts(buffer.as_mut_ptr(), buffer.len(), alignment) }
+    }
+
+    /// Returns `None` if `alignment` is not a power of two or `size` exceeds `isize::MAX`.
+    ///
+    /// # Safety
+    ///
+    /// `base` must be valid for reads and writes of `size` bytes for `'a` and not be
+    /// accessed through any other pointer while the region is in use.
+    pub unsafe fn from_raw_parts(base: *mut u8, size: usize, alignment: usize) -> Option<Self> {
+        if !alignment.is_power_of_two() || size > isize::MAX as usize {
+            return None;
+        }
+        let padding = base.align_offset(alignment).min(size);
+        Some(Self {
+            arena: Arena::new(),
+            memory: Memory {
+                base: base.wrapping_add(padding),
+                size: size - padding,
+                alignment,
+            },
+            allocator: Cell::new(Allocator::from_context::<Self>(core::ptr::null_mut())),
+            _buffer: PhantomData,
+            _pinned: PhantomPinned,
+        })
+    }
+
+    fn memory(self: &Self) -> Memory {
+        self.memory
+    }
+}
+
+arena_allocator!(['a] BumpRegion<'a> where []);
+
+/// Creates a `BumpRegion` at the start of `buffer` that allocates from the rest of it.
+///
+/// Returns null if `alignment` is not a power of two or `buffer` is too small to hold the region.
+///
+/// # Safety
+///
+/// `buffer` must be valid for reads and writes of `size` bytes until `bump_destroy` is called
+/// and must not be accessed through any other pointer in the meantime.
+#[no_mangle]
+pub unsafe extern "C" fn bump_create(buffer: *mut c_void, size: usize, alignment: usize) -> AllocatorHandle {
+    let buffer = buffer as *mut u8;
+    if buffer.is_null() {
+        return core::ptr::null();
+    }
+    let padding = buffer.align_offset(core::mem::align_of::<BumpRegion>());
+    let Some(heap_offset) = padding
+        .checked_add(core::mem::size_of::<BumpRegion>())
+        .filter(|heap_offset| *heap_offset <= size)
+    else {
+        return core::ptr::null();
+    };
+    let Some(region) = BumpRegion::from_raw_parts(buffer.add(heap_offset), size - heap_offset, alignment) else {
+        return core::ptr::null();
+    };
+    let location = buffer.add(padding) as *mut BumpRegion<'static>;
+    location.write(region);
+    //the context keeps the write access of `location`, which `bump_destroy` drops the region through
+    let allocator = &(*location).allocator;
+    allocator.set(Allocator::from_context::<BumpRegion>(location as *mut c_void));
+    allocator.as_ptr()
+}
+
+/// Drops a region created by `bump_create` and returns the number of allocations that were
+/// never freed. The drop-without-free handler, see `bump_handle_drop_without_free`, is called
+/// with that number if it is not zero.
+///
+/// # Safety
+///
+/// `handle` must be null or have been returned by `bump_create` and not destroyed yet.
+#[no_mangle]
+pub unsafe extern "C" fn bump_destroy(handle: AllocatorHandle) -> usize {
+    //ignored like free(NULL)
+    if handle.is_null() {
+        return 0;
+    }
+    let region = (*handle).context as *mut BumpRegion<'static>;
+    let count = (*region).get_count();
+    core::ptr::drop_in_place(region);
+    count
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::MallocFree;
+
+    #[test]
+    fn region_malloc_free() {
+        let mut buffer = [0u64; 33];
+        let bytes = unsafe { core::slice::from_raw_parts_mut((buffer.as_mut_ptr() as *mut u8).add(1), 256) };
+        let base = bytes.as_ptr() as usize;
+        let region = BumpRegion::new(bytes, 16).unwrap();
+
+        let first = region.malloc(20);
+        assert_eq!(first as usize % 16, 0);
+        assert!(first as usize >= base && (first as usize) < base + 16);
+        let second = region.malloc(20);
+        assert_eq!(second as usize, first as usize + 32);
+        assert_eq!(region.malloc(256), core::ptr::null_mut());
+        unsafe {
+            region.free(first);
+            region.free(second);
+        }
+        assert_eq!(region.get_count(), 0);
+        assert_eq!(region.malloc(8), first);
+        unsafe { region.free(first) };
+    }
+
+    #[test]
+    fn region_rejects_bad_alignment() {
+        let mut buffer = [0u8; 64];
+        assert!(BumpRegion::new(&mut buffer, 0).is_none());
+        assert!(BumpRegion::new(&mut buffer, 12).is_none());
+    }
+
+    #[test]
+    fn region_extern_c() {
+        let mut buffer = [0u8; 128];
+        let region = core::pin::pin!(BumpRegion::new(&mut buffer, 8).unwrap());
+        let handle = region.as_ref().get_handle();
+        unsafe {
+            let ptr = crate::bump_calloc(handle, 2, 8);
+            assert!(!ptr.is_null());
+            assert_eq!(crate::bump_status(handle).count, 1);
+            crate::bump_free(handle, ptr);
+        }
+        assert_eq!(region.get_count(), 0);
+    }
+
+    #[test]
+    fn region_create_destroy() {
+        let mut buffer = vec![0u8; 512];
+        unsafe {
+            assert!(bump_create(buffer.as_mut_ptr() as *mut c_void, 16, 8).is_null());
+            assert!(bump_create(buffer.as_mut_ptr() as *mut c_void, 512, 3).is_null());
+
+            let handle = bump_create(buffer.as_mut_ptr() as *mut c_void, 512, 32);
+            assert!(!handle.is_null());
+            let region = buffer.as_ptr_range();
+            let first = crate::bump_malloc(handle, 64);
+            assert_eq!(first as usize % 32, 0);
+            assert!(region.contains(&(first as *const u8)));
+            unsafe extern "C" fn on_drop_without_free(context: *mut c_void, count: usize) {
+                *(context as *mut usize) = count;
+            }
+            let mut leaked = 0usize;
+            crate::bump_handle_drop_without_free(handle, Some(on_drop_without_free), core::ptr::addr_of_mut!(leaked) as *mut c_void);
+            //leaked on purpose
+            crate::bump_malloc(handle, 64);
+            crate::bump_free(handle, first);
+            assert_eq!(crate::bump_status(handle).count, 1);
+            assert_eq!(bump_destroy(handle), 1);
+            assert_eq!(leaked, 1);
+
+            let handle = bump_create(buffer.as_mut_ptr() as *mut c_void, 512, 32);
+            assert_eq!(crate::bump_malloc(handle, 64), first);
+            crate::bump_free(handle, first);
+            assert_eq!(bump_destroy(handle), 0);
+            assert_eq!(bump_destroy(core::ptr::null()), 0);
+        }
+    }
+}
@@ -0,0 +1,131 @@
+use core::cell::Cell;
+use core::ffi::c_void;
+use core::marker::PhantomPinned;
+use core::pin::Pin;
+
+use crate::{Allocator, AllocatorHandle, MallocFree, Marker, OnChangedC, OnDropWithoutFreeC, Status};
+
+/// Allocator passed to the closure of `scope`, everything allocated through it is freed when
+/// the closure returns.
+pub struct Scope<'a, M: MallocFree> {
+    malloc_free: &'a M,
+    allocator: Cell<Allocator>,
+    _pinned: PhantomPinned,
+}
+
+impl<'a, M: MallocFree> Scope<'a, M> {
+    pub(crate) fn new(malloc_free: &'a M) -> Self {
+        Self {
+            malloc_free,
+            allocator: Cell::new(Allocator::from_context::<Self>(core::ptr::null_mut())),
+            _pinned: PhantomPinned,
+        }
+    }
+
+    /// Returns a handle that stays valid until the closure returns.
+    pub fn get_handle(self: Pin<&Self>) -> AllocatorHandle {
+        let scope = self.get_ref();
+        scope.allocator.set(Allocator::new(self));
+        scope.allocator.as_ptr()
+    }
+}
+
+//every block comes from `malloc_free`, which upholds the contract
+unsafe impl<M: MallocFree> MallocFree for Scope<'_, M> {
+    fn malloc(self: &Self, size: usize) -> *mut c_void {
+        self.malloc_free.malloc(size)
+    }
+
+    unsafe fn free(self: &Self, ptr: *mut c_void) {
+        self.malloc_free.free(ptr)
+    }
+
+    unsafe fn realloc(self: &Self, ptr: *mut c_void, size: usize) -> *mut c_void {
+        self.malloc_free.realloc(ptr, size)
+    }
+
+    fn get_status(self: &Self) -> Status {
+        self.malloc_free.get_status()
+    }
+
+    fn calloc(self: &Self, nmemb: usize, size: usize) -> *mut c_void {
+        self.malloc_free.calloc(nmemb, size)
+    }
+
+    fn aligned_malloc(self: &Self, size: usize, alignment: usize) -> *mut c_void {
+        self.malloc_free.aligned_malloc(size, alignment)
+    }
+
+    unsafe fn aligned_realloc(self: &Self, ptr: *mut c_void, size: usize, alignment: usize) -> *mut c_void {
+        self.malloc_free.aligned_realloc(ptr, size, alignment)
+    }
+
+    fn mark(self: &Self) -> Marker {
+        self.malloc_free.mark()
+    }
+
+    unsafe fn release(self: &Self, marker: Marker) -> bool {
+        self.malloc_free.release(marker)
+    }
+
+    unsafe fn handle_on_changed_c(self: &Self, observer: Option<OnChangedC>, context: *mut c_void) {
+        self.malloc_free.handle_on_changed_c(observer, context)
+    }
+
+    unsafe fn handle_drop_without_free_c(self: &Self, handler: Option<OnDropWithoutFreeC>, context: *mut c_void) {
+        self.malloc_free.handle_drop_without_free_c(handler, context)
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use crate::tests::Events;
+    use crate::{Action, Bump, BumpBox, BumpRegion, MallocFree};
+
+    #[test]
+    fn scope_frees_on_return() {
+        let events = Events::default();
+        let mut bump = Bump::<1024, 8>::new();
+        bump.handle_on_changed_boxed(events.handler());
+        let kept = bump.malloc(8);
+        for frame in 0..3 {
+            let usage = bump.scope(|scoped| {
+                let handle = scoped.get_handle();
+                unsafe { crate::bump_malloc(handle, 100) };
+                let value = BumpBox::new_in(frame, &*scoped).unwrap();
+                assert_eq!(*value, frame);
+                //markers left behind inside the scope are released with it
+                scoped.mark();
+                scoped.malloc(16);
+                scoped.get_status().usage
+            });
+            assert!(usage > bump.get_status().usage);
+            assert_eq!(bump.get_count(), 1);
+        }
+        assert_eq!(events.count(Action::Release), 3);
+        assert_eq!(bump.arena.depth.get(), 0);
+        unsafe { bump.free(kept) };
+        assert_eq!(bump.arena.head.get(), 0);
+
+        let mut buffer = [0u8; 256];
+        let mut region = BumpRegion::new(&mut buffer, 8).unwrap();
+        region.scope(|scoped| scoped.malloc(64));
+        assert_eq!(region.get_count(), 0);
+    }
+
+    #[test]
+    fn scope_frees_on_panic() {
+        let mut bump = Bump::<1024, 8>::new();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            bump.scope(|scoped| {
+                scoped.malloc(64);
+                panic!("frame failed");
+            })
+        }));
+        assert!(result.is_err());
+        assert_eq!(bump.get_count(), 0);
+        assert_eq!(bump.get_status().usage, 0);
+        assert_eq!(bump.arena.depth.get(), 0);
+        assert!(bump.reset());
+    }
+}
@@ -0,0 +1,146 @@
+use core::alloc::Layout;
+use core::ffi::c_void;
+use core::mem::size_of;
+use core::ops::{Deref, DerefMut};
+use core::ptr::NonNull;
+
+use crate::MallocFree;
+
+/// Growable array in a `MallocFree` heap that frees its block on drop.
+///
+/// Growing the most recent block of a `Bump` happens in place.
+pub struct BumpVec<'a, T, M: MallocFree> {
+    ptr: NonNull<T>,
+    length: usize,
+    capacity: usize,
+    malloc_free: &'a M,
+}
+
+impl<'a, T, M: MallocFree> BumpVec<'a, T, M> {
+    /// Does not allocate until the first push.
+    pub fn new_in(malloc_free: &'a M) -> Self {
+        Self {
+            ptr: NonNull::dangling(),
+            length: 0,
+            //zero sized values never need a block
+            capacity: if size_of::<T>() == 0 { usize::MAX } else { 0 },
+            malloc_free,
+        }
+    }
+
+    /// Returns `None` if the heap is full.
+    pub fn with_capacity_in(capacity: usize, malloc_free: &'a M) -> Option<Self> {
+        let mut vec = Self::new_in(malloc_free);
+        if capacity > vec.capacity && !vec.grow(capacity) {
+            return None;
+        }
+        Some(vec)
+    }
+
+    pub fn capacity(self: &Self) -> usize {
+        self.capacity
+    }
+
+    /// Hands `value` back if the heap is full.
+    pub fn push(self: &mut Self, value: T) -> Result<(), T> {
+        if self.length == self.capacity {
+            let Some(capacity) = self.capacity.checked_mul(2) else {
+                return Err(value);
+            };
+            if !self.grow(capacity.max(4)) {
+                return Err(value);
+            }
+        }
+        unsafe { self.ptr.as_ptr().add(self.length).write(value) };
+        self.length += 1;
+        Ok(())
+    }
+
+    pub fn pop(self: &mut Self) -> Option<T> {
+        if self.length == 0 {
+            return None;
+        }
+        self.length -= 1;
+        Some(unsafe { self.ptr.as_ptr().add(self.length).read() })
+    }
+
+    /// Drops the values but keeps the block.
+    pub fn clear(self: &mut Self) {
+        let values: *mut [T] = &mut **self;
+        self.length = 0;
+        unsafe { core::ptr::drop_in_place(values) };
+    }
+
+    fn grow(self: &mut Self, capacity: usize) -> bool {
+        let Ok(layout) = Layout::array::<T>(capacity) else {
+            return false;
+        };
+        let block = if self.capacity == 0 { core::ptr::null_mut() } else { self.ptr.as_ptr() as *mut c_void };
+        let ptr = unsafe { self.malloc_free.aligned_realloc(block, layout.size(), layout.align()) };
+        let Some(ptr) = NonNull::new(ptr as *mut T) else {
+            return false;
+        };
+        self.ptr = ptr;
+        self.capacity = capacity;
+        true
+    }
+}
+
+impl<T, M: MallocFree> Deref for BumpVec<'_, T, M> {
+    type Target = [T];
+
+    fn deref(self: &Self) -> &[T] {
+        unsafe { core::slice::from_raw_parts(self.ptr.as_ptr(), self.length) }
+    }
+}
+
+impl<T, M: MallocFree> DerefMut for BumpVec<'_, T, M> {
+    fn deref_mut(self: &mut Self) -> &mut [T] {
+        unsafe { core::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.length) }
+    }
+}
+
+impl<T, M: MallocFree> Drop for BumpVec<'_, T, M> {
+    fn drop(self: &mut Self) {
+        self.clear();
+        if size_of::<T>() != 0 && self.capacity > 0 {
+            unsafe { self.malloc_free.free(self.ptr.as_ptr() as *mut c_void) };
+        }
+    }
+}
+
+#[cfg(all(test, feature = "std"))]
+mod tests {
+    use super::*;
+    use crate::Bump;
+
+    #[test]
+    fn vec_grows_and_frees_on_drop() {
+        let bump = Bump::<1024, 8>::new();
+        let mut words = BumpVec::new_in(&bump);
+        assert_eq!(bump.get_count(), 0);
+        for word in ["a", "b", "c", "d", "e"] {
+            words.push(String::from(word)).unwrap();
+        }
+        //the only block grows in place
+        assert_eq!(words.capacity(), 8);
+        assert_eq!(bump.get_count(), 1);
+        assert_eq!(words.pop().as_deref(), Some("e"));
+        words[0].push('!');
+        assert_eq!(words.join(""), "a!bcd");
+
+        let mut full = BumpVec::with_capacity_in(1, &bump).unwrap();
+        full.push([1u8; 512]).unwrap();
+        assert_eq!(full.push([2u8; 512]), Err([2u8; 512]));
+        assert_eq!(full.len(), 1);
+        drop(full);
+        drop(words);
+        assert_eq!(bump.get_count(), 0);
+
+        let mut units = BumpVec::new_in(&bump);
+        for _ in 0..100 {
+            units.push(()).unwrap();
+        }
+        assert_eq!((units.len(), bump.get_count()), (100, 0));
+    }
+}